@@ -0,0 +1,155 @@
+use expr::lambda::LambdaExpr;
+use expr::debruijn::DbExpr;
+
+use std::fmt;
+use std::hash::{Hash, Hasher};
+
+fn position(names: &[&str], var: &str) -> Option<usize> {
+    names.iter().rev().position(|n| *n == var)
+}
+
+/// The `n`th name in the sequence a, b, ..., z, a1, b1, ...
+pub(crate) fn canonical_name(n: usize) -> String {
+    let letter = (b'a' + (n % 26) as u8) as char;
+    if n < 26 {
+        letter.to_string()
+    } else {
+        format!("{}{}", letter, n / 26)
+    }
+}
+
+fn canonical_hints(mut expr: DbExpr, depth: usize) -> DbExpr {
+    match expr {
+        DbExpr::Apply(ref mut e1, ref mut e2) => DbExpr::apply(canonical_hints(e1.take(), depth), canonical_hints(e2.take(), depth)),
+        DbExpr::Lambda(_, ref mut e) => DbExpr::Lambda(canonical_name(depth), Box::new(canonical_hints(e.take(), depth + 1))),
+        _ => expr,
+    }
+}
+
+impl LambdaExpr {
+    /// Whether the two terms are equal up to renaming of bound variables.
+    pub fn alpha_eq(&self, other: &LambdaExpr) -> bool {
+        self.alpha_eq_in(other, &mut Vec::new(), &mut Vec::new())
+    }
+
+    fn alpha_eq_in<'a>(&'a self, other: &'a LambdaExpr, names1: &mut Vec<&'a str>, names2: &mut Vec<&'a str>) -> bool {
+        match (self, other) {
+            (LambdaExpr::Variable(v1), LambdaExpr::Variable(v2)) =>
+                match (position(names1, v1), position(names2, v2)) {
+                    (None, None) => v1 == v2,
+                    (i, j) => i == j,
+                },
+            (LambdaExpr::Apply(f1, a1), LambdaExpr::Apply(f2, a2)) =>
+                f1.alpha_eq_in(f2, names1, names2) && a1.alpha_eq_in(a2, names1, names2),
+            (LambdaExpr::Lambda(v1, e1), LambdaExpr::Lambda(v2, e2)) => {
+                names1.push(v1);
+                names2.push(v2);
+                let res = e1.alpha_eq_in(e2, names1, names2);
+                names1.pop();
+                names2.pop();
+                res
+            },
+            _ => false,
+        }
+    }
+
+    /// Renames bound variables so that alpha-equivalent terms become equal.
+    /// Binders are named by nesting depth (`a`, `b`, ...), with a numeric
+    /// suffix where that would capture a free variable.
+    pub fn canonical(&self) -> LambdaExpr {
+        LambdaExpr::from(canonical_hints(DbExpr::from(self.clone()), 0))
+    }
+
+    fn hash_in<'a, H: Hasher>(&'a self, names: &mut Vec<&'a str>, state: &mut H) {
+        match self {
+            LambdaExpr::Variable(v) => match position(names, v) {
+                Some(i) => { 0u8.hash(state); i.hash(state); },
+                None => { 1u8.hash(state); v.hash(state); },
+            },
+            LambdaExpr::Apply(e1, e2) => {
+                2u8.hash(state);
+                e1.hash_in(names, state);
+                e2.hash_in(names, state);
+            },
+            LambdaExpr::Lambda(v, e) => {
+                3u8.hash(state);
+                names.push(v);
+                e.hash_in(names, state);
+                names.pop();
+            },
+        }
+    }
+}
+
+/// Wrapper comparing and hashing terms up to alpha-equivalence,
+/// so that e.g. `λx x` and `λy y` are the same map key.
+#[derive(Debug, Clone)]
+pub struct Alpha(pub LambdaExpr);
+
+impl PartialEq for Alpha {
+    fn eq(&self, other: &Alpha) -> bool {
+        self.0.alpha_eq(&other.0)
+    }
+}
+
+impl Eq for Alpha {}
+
+impl Hash for Alpha {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.0.hash_in(&mut Vec::new(), state)
+    }
+}
+
+impl fmt::Display for Alpha {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        write!(f, "{}", self.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alpha::*;
+    use expr::lambda::parse;
+
+    use std::collections::HashSet;
+
+    #[test]
+    fn alpha_eq_identity() {
+        assert!(parse("\\x x").unwrap().alpha_eq(&parse("\\y y").unwrap()));
+        assert!(!parse("\\x x").unwrap().alpha_eq(&parse("\\x y").unwrap()));
+    }
+
+    #[test]
+    fn alpha_eq_free_variables() {
+        assert!(!parse("\\x y").unwrap().alpha_eq(&parse("\\y y").unwrap()));
+        assert!(parse("\\x y x").unwrap().alpha_eq(&parse("\\z y z").unwrap()));
+    }
+
+    #[test]
+    fn alpha_eq_shadowing() {
+        assert!(parse("\\x \\x x").unwrap().alpha_eq(&parse("\\a \\b b").unwrap()));
+        assert!(!parse("\\x \\x x").unwrap().alpha_eq(&parse("\\a \\b a").unwrap()));
+    }
+
+    #[test]
+    fn alpha_eq_reduction() {
+        let (e, _) = parse("(\\x \\y x) y").unwrap().repeated_beta(10);
+        assert!(e.alpha_eq(&parse("\\z y").unwrap()));
+    }
+
+    #[test]
+    fn canonical_names() {
+        assert_eq!(parse("\\a \\b a b").unwrap(), parse("\\m \\n m n").unwrap().canonical());
+        assert_eq!(parse("\\a1 \\b a a1").unwrap(), parse("\\x \\y a x").unwrap().canonical());
+    }
+
+    #[test]
+    fn alpha_hash() {
+        let mut set = HashSet::new();
+        set.insert(Alpha(parse("\\x \\y x").unwrap()));
+        set.insert(Alpha(parse("\\a \\b a").unwrap()));
+        set.insert(Alpha(parse("\\a \\b b").unwrap()));
+        assert_eq!(2, set.len());
+        assert!(set.contains(&Alpha(parse("\\p \\q p").unwrap())));
+    }
+}
@@ -1,24 +1,158 @@
 extern crate lambda;
 
 use lambda::expr;
+use lambda::interpret::Strategy;
+use lambda::encoding::Encoding;
+use lambda::readback;
+use lambda::program;
+use lambda::prelude;
+use lambda::graph;
+use lambda::krivine;
+use lambda::cek;
+use lambda::types;
 
 use std::io;
-use std::io::{Read, Write};
+use std::io::Write;
 use std::fs;
+use std::path::Path;
+
+/// Steps a CEK machine on `e`, printing each state, until it finishes
+/// (returning the value) or the user leaves (returning `None`).
+fn step_cek(e: &expr::lambda::LambdaExpr) -> Result<Option<expr::lambda::LambdaExpr>, String> {
+    enum Command {
+        Step(u32),
+        /// Steps to the end, or until the limit on beta-reductions.
+        Run(u32),
+    }
+    println!("call-by-value CEK machine: enter or 'step [TIMES]' to step, 'run [LIMIT]' to finish, 'quit' to leave");
+    let mut state = cek::State::new(e);
+    println!("{}", state);
+    let mut line = String::new();
+    loop {
+        print!("cek> ");
+        io::stdout().flush().map_err(|err| format!("error flushing stdout: {}", err))?;
+        line.clear();
+        io::stdin().read_line(&mut line).map_err(|err| format!("error reading from stdin: {}", err))?;
+        if line.is_empty() {
+            // end of file
+            println!();
+            return Ok(None);
+        }
+        let command = line.trim();
+        let command = if command == "quit" {
+            return Ok(None);
+        } else if let Some(limit_str) = command.strip_prefix("run") {
+            match parse_limit(limit_str) {
+                Ok(limit) => Command::Run(limit),
+                Err(err) => {
+                    eprintln!("{}", err);
+                    continue
+                },
+            }
+        } else if let Some(times_str) = command.strip_prefix("step").or(if command.is_empty() { Some("") } else { None }) {
+            let times_str = times_str.trim();
+            if times_str.is_empty() {
+                Command::Step(1)
+            } else {
+                match times_str.parse::<u32>() {
+                    Ok(times) => Command::Step(times),
+                    Err(err) => {
+                        eprintln!("invalid number: {}", err);
+                        continue
+                    }
+                }
+            }
+        } else {
+            eprintln!("unrecognized command");
+            continue
+        };
+        match command {
+            Command::Step(times) => for _ in 0..times {
+                if !state.step() {
+                    break;
+                }
+                println!("{}", state);
+            },
+            Command::Run(limit) => {
+                let finished = state.run(limit);
+                println!("{}", state);
+                if !finished {
+                    eprintln!("no value found within {} reductions", limit);
+                }
+            },
+        }
+        if let Some(value) = state.result() {
+            eprintln!("reduced {} {}", state.reductions, if state.reductions == 1 { "time" } else { "times" });
+            println!("{}", value);
+            return Ok(Some(value));
+        }
+    }
+}
+
+/// Reduction limit given as the argument of a command, or 1000000 if
+/// there is none.
+fn parse_limit(limit_str: &str) -> Result<u32, String> {
+    let limit_str = limit_str.trim();
+    if limit_str.is_empty() {
+        return Ok(1000000);
+    }
+    limit_str.parse().map_err(|err| format!("invalid number: {}", err))
+}
+
+/// Type of `expr_str`, or of `working` if it is empty, with `%` referring
+/// to `working`. Definitions are bound with `let`, so each one is typed once
+/// and each use of it, e.g. from the prelude, can have its own type.
+fn type_of(expr_str: &str, working: Option<&expr::lambda::LambdaExpr>, definitions: &[expr::lambda::Definition],
+           encoding: Encoding) -> Result<types::Typing, String> {
+    let e = if expr_str.is_empty() {
+        working.ok_or("no expression")?.clone()
+    } else {
+        match expr::lambda::parse_program_with(expr_str, encoding) {
+            Ok(ref p) if !p.imports.is_empty() || p.body.is_none() => return Err("expected a single expression".to_string()),
+            Ok(p) => {
+                let mut definitions = definitions.to_vec();
+                definitions.extend(p.definitions);
+                let mut e = expr::lambda::bind_definitions(&definitions, p.body.unwrap());
+                if let Some(old) = working {
+                    e = e.replace("%", old);
+                }
+                e
+            },
+            Err(err) => return Err(format!("syntax error at {}", err.render(expr_str))),
+        }
+    };
+    e.type_of().map_err(|err| format!("not simply typeable: {}", err))
+}
 
 fn main() {
     println!("beta reduction calculator");
     println!();
     println!("load FILE");
+    println!("loadf FILE");
     println!("set EXPR");
     println!("beta [TIMES]");
+    println!("need [LIMIT]");
+    println!("krivine [whnf] [LIMIT]");
+    println!("cek");
+    println!("strategy [NAME]");
+    println!("encoding [NAME]");
     println!("print");
+    println!("readback [on|off]");
+    println!(":type [EXPR]");
     println!("quit");
     println!();
     println!("% refers to the current expression");
+    println!("definitions from 'load' and 'set NAME = EXPR' can be used in later expressions,");
+    println!("as can those of the prelude (true, false, zero, succ, add, ...)");
     println!();
     let mut line = String::new();
     let mut working: Option<expr::lambda::LambdaExpr> = None;
+    let mut strategy = Strategy::NormalOrder;
+    let mut encoding = Encoding::Church;
+    let mut show_values = false;
+    let mut definitions: Vec<expr::lambda::Definition> = prelude::definitions();
+    // the definitions before this index are the prelude's
+    let mut prelude_len = definitions.len();
     loop {
         print!("lc> ");
         if let Err(err) = io::stdout().flush() {
@@ -30,18 +164,24 @@ fn main() {
             eprintln!("error reading from stdin: {}", err);
             return;
         }
-        if line.len() == 0 {
+        if line.is_empty() {
             // end of file
             println!();
             return;
         }
-        while line.ends_with(|c| c == '\r' || c == '\n') { line.pop().unwrap(); }
-        if line.len() == 0 { continue; }
+        while line.ends_with(['\r', '\n']) { line.pop().unwrap(); }
+        if line.is_empty() { continue; }
         if line == "quit" {
             return;
         } else if line == "print" {
             if let Some(ref e) = working {
                 println!("{}", e);
+                if show_values {
+                    let values: Vec<String> = readback::readback(e, encoding).iter().map(|v| v.to_string()).collect();
+                    if !values.is_empty() {
+                        println!("= {}", values.join(" = "));
+                    }
+                }
                 if let Err(err) = io::stdout().flush() {
                     eprintln!("error flushing stdout: {}", err);
                     return;
@@ -49,46 +189,78 @@ fn main() {
             } else {
                 eprintln!("no expression");
             }
-        } else if line.starts_with("load ") {
-            let filename = &line[5..];
-            let mut f = match fs::File::open(filename) {
-                Ok(f) => f,
+        } else if let Some(filename) = line.strip_prefix("load ") {
+            match program::load_with(Path::new(filename), encoding) {
+                Ok(mut p) => {
+                    definitions.append(&mut p.definitions);
+                    if let Some(e) = p.body {
+                        let mut next = expr::lambda::bind_definitions(&definitions, e);
+                        if let Some(old) = working {
+                            next = next.replace("%", &old);
+                        }
+                        working = Some(next);
+                    }
+                },
+                Err(err) => { eprintln!("{}", err); },
+            }
+        } else if let Some(filename) = line.strip_prefix("loadf ") {
+            let contents = match fs::read_to_string(filename) {
+                Ok(c) => c,
                 Err(err) => {
-                    eprintln!("error opening '{}' for reading: {}", filename, err);
-                    return
+                    eprintln!("error reading '{}': {}", filename, err);
+                    continue
                 },
             };
-            let mut contents = String::new();
-            if let Err(err) = f.read_to_string(&mut contents) {
-                eprintln!("error reading file '{}': {}", filename, err);
-                return;
-            }
-            match expr::lambda::parse(&contents) {
-                Ok(e) => {
-                    let mut next = e;
-                    if let Some(old) = working {
-                        next = next.replace("%", &old);
+            let p = match expr::systemf::parse_program(&contents) {
+                Ok(p) => p,
+                Err(err) => {
+                    eprintln!("syntax error in '{}' at {}", filename, err.render(&contents));
+                    continue
+                },
+            };
+            match p.type_check() {
+                Ok((types, body)) => {
+                    for (def, t) in p.definitions.iter().zip(types) {
+                        println!("{} : {}", def.name, t);
                     }
-                    working = Some(next);
+                    if let Some(t) = body {
+                        println!("% : {}", t);
+                    }
+                },
+                Err(err) => {
+                    eprintln!("type error in '{}': {}", filename, err);
+                    continue
                 },
-                Err(err) => { eprintln!("syntax error in '{}': {}", filename, err); },
             }
-        } else if line.starts_with("set ") {
-            let expr_str = &line[4..];
-            match expr::lambda::parse(expr_str) {
-                Ok(e) => {
-                    let mut next = e;
-                    if let Some(old) = working {
-                        next = next.replace("%", &old);
+            if let Some(mut next) = p.erase() {
+                if let Some(old) = working {
+                    next = next.replace("%", &old);
+                }
+                working = Some(next);
+            }
+            definitions.extend(p.definitions.into_iter().map(|def| expr::lambda::Definition {
+                name: def.name,
+                value: def.value.into(),
+            }));
+        } else if let Some(expr_str) = line.strip_prefix("set ") {
+            match expr::lambda::parse_program_with(expr_str, encoding) {
+                Ok(ref p) if !p.imports.is_empty() => { eprintln!("use 'load' for files with imports"); },
+                Ok(mut p) => {
+                    definitions.append(&mut p.definitions);
+                    if let Some(e) = p.body {
+                        let mut next = expr::lambda::bind_definitions(&definitions, e);
+                        if let Some(old) = working {
+                            next = next.replace("%", &old);
+                        }
+                        working = Some(next);
                     }
-                    working = Some(next);
                 },
-                Err(err) => { eprintln!("syntax error: {}", err); },
+                Err(err) => { eprintln!("syntax error at {}", err.render(expr_str)); },
             }
-        } else if line.starts_with("beta") {
+        } else if let Some(times_str) = line.strip_prefix("beta") {
             let times = {
-                if line.len() > 4 {
-                    let times_str = &line[4..].trim();
+                let times_str = times_str.trim();
+                if !times_str.is_empty() {
                     match times_str.parse::<u32>() {
                         Ok(times) => times,
                         Err(err) => {
@@ -99,14 +271,152 @@ fn main() {
                 } else { 1 }
             };
             if let Some(e) = working {
-                let (next, app_times) = e.repeated_beta(times);
+                let (next, app_times) = e.repeated_beta_with(strategy, times);
                 eprintln!("reduced {} {}", app_times, if app_times == 1 { "time" } else { "times" });
                 working = Some(next);
             } else {
                 eprintln!("no expression");
             }
+        } else if let Some(limit_str) = line.strip_prefix("need") {
+            let limit = match parse_limit(limit_str) {
+                Ok(limit) => limit,
+                Err(err) => {
+                    eprintln!("{}", err);
+                    continue
+                },
+            };
+            if let Some(ref e) = working {
+                match graph::normalize(e, limit) {
+                    Some((next, app_times)) => {
+                        eprintln!("reduced {} {}", app_times, if app_times == 1 { "time" } else { "times" });
+                        working = Some(next);
+                    },
+                    None => eprintln!("no normal form found within {} reductions", limit),
+                }
+            } else {
+                eprintln!("no expression");
+            }
+        } else if let Some(args) = line.strip_prefix("krivine") {
+            let args = args.trim();
+            let (weak, limit_str) = match args.strip_prefix("whnf") {
+                Some(rest) => (true, rest.trim()),
+                None => (false, args),
+            };
+            let limit = match parse_limit(limit_str) {
+                Ok(limit) => limit,
+                Err(err) => {
+                    eprintln!("{}", err);
+                    continue
+                },
+            };
+            if let Some(ref e) = working {
+                let result = if weak { krivine::whnf(e, limit) } else { krivine::normalize(e, limit) };
+                match result {
+                    Some((next, app_times)) => {
+                        eprintln!("reduced {} {}", app_times, if app_times == 1 { "time" } else { "times" });
+                        working = Some(next);
+                    },
+                    None => eprintln!("no normal form found within {} reductions", limit),
+                }
+            } else {
+                eprintln!("no expression");
+            }
+        } else if line == "cek" {
+            let e = match working {
+                Some(ref e) => e.clone(),
+                None => {
+                    eprintln!("no expression");
+                    continue
+                },
+            };
+            match step_cek(&e) {
+                Ok(Some(next)) => working = Some(next),
+                Ok(None) => {},
+                Err(err) => {
+                    eprintln!("{}", err);
+                    return;
+                },
+            }
+        } else if let Some(name) = line.strip_prefix("strategy") {
+            let name = name.trim();
+            if name.is_empty() {
+                let names: Vec<&str> = Strategy::all().iter().map(Strategy::name).collect();
+                println!("{} (available: {})", strategy, names.join(", "));
+            } else {
+                match name.parse() {
+                    Ok(st) => strategy = st,
+                    Err(err) => { eprintln!("{}", err); },
+                }
+            }
+        } else if let Some(expr_str) = line.strip_prefix(":type") {
+            match type_of(expr_str.trim(), working.as_ref(), &definitions, encoding) {
+                Ok(t) => println!("{}", t),
+                Err(err) => eprintln!("{}", err),
+            }
+        } else if let Some(mode) = line.strip_prefix("readback") {
+            match mode.trim() {
+                "" => println!("{}", if show_values { "on" } else { "off" }),
+                "on" => show_values = true,
+                "off" => show_values = false,
+                mode => eprintln!("unknown readback mode: {}", mode),
+            }
+        } else if let Some(name) = line.strip_prefix("encoding") {
+            let name = name.trim();
+            if name.is_empty() {
+                let names: Vec<&str> = Encoding::all().iter().map(Encoding::name).collect();
+                println!("{} (available: {})", encoding, names.join(", "));
+            } else {
+                match name.parse() {
+                    Ok(enc) => {
+                        // numerals and lists of the prelude follow the literals
+                        encoding = enc;
+                        let rest = definitions.split_off(prelude_len);
+                        definitions = prelude::definitions_with(encoding);
+                        prelude_len = definitions.len();
+                        definitions.extend(rest);
+                    },
+                    Err(err) => { eprintln!("{}", err); },
+                }
+            }
         } else {
             eprintln!("unrecognized command");
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn type_str(expr_str: &str, working: Option<&expr::lambda::LambdaExpr>) -> String {
+        match type_of(expr_str, working, &prelude::definitions(), Encoding::Church) {
+            Ok(t) => t.to_string(),
+            Err(err) => err,
+        }
+    }
+
+    #[test]
+    fn type_of_prelude() {
+        // each use of a definition gets its own type
+        assert_eq!("a -> b -> b", type_str("not true", None));
+        assert_eq!("(a -> a) -> a -> a", type_str("add 1 (mul 2 3)", None));
+        assert_eq!("a -> a", type_str("id id", None));
+        assert!(type_str("Y", None).starts_with("not simply typeable"));
+        assert_eq!("a -> a", type_str("let id = \\x x in id id", None));
+    }
+
+    #[test]
+    fn type_of_working() {
+        let working = expr::lambda::parse("\\x x").unwrap();
+        assert_eq!("a -> a", type_str("", Some(&working)));
+        assert_eq!("a -> b -> b", type_str("const %", Some(&working)));
+        assert_eq!("no expression", type_str("", None));
+    }
+
+    #[test]
+    fn limits() {
+        assert_eq!(Ok(1000000), parse_limit(""));
+        assert_eq!(Ok(25), parse_limit(" 25 "));
+        assert!(parse_limit("x").unwrap_err().starts_with("invalid number"));
+    }
+}
@@ -1,6 +1,7 @@
 extern crate lambda;
 
 use lambda::expr;
+use lambda::convert::Abstraction;
 
 use std::io;
 use std::io::Write;
@@ -8,7 +9,11 @@ use std::io::Write;
 fn main() {
     println!("lambda calculus to iota converter");
     println!("type 'quit' to quit");
+    println!("type 'algorithm NAME' to change how lambdas are eliminated");
+    println!("type 'sizes EXPR' to compare the combinator size under each algorithm");
+    println!("type 'iota PROGRAM' to convert an iota program back to lambda calculus");
     let mut line = String::new();
+    let mut algorithm = Abstraction::Basic;
     loop {
         print!("lc> ");
         if let Err(err) = io::stdout().flush() {
@@ -20,7 +25,7 @@ fn main() {
             eprintln!("error reading from stdin: {}", err);
             return;
         }
-        if line.len() == 0 {
+        if line.is_empty() {
             // end of file
             println!();
             return;
@@ -29,21 +34,58 @@ fn main() {
         if line == "quit" {
             return;
         }
+        if let Some(name) = line.strip_prefix("algorithm ") {
+            match name.trim().parse() {
+                Ok(alg) => algorithm = alg,
+                Err(err) => { eprintln!("{}", err); },
+            }
+            continue
+        }
+        if let Some(program) = line.strip_prefix("iota ") {
+            match expr::iota::parse(program) {
+                Ok(iota) => println!("{} => {}", iota.to_string(), iota.decompile()),
+                Err(err) => { eprintln!("syntax error at {}", err.render(program)); },
+            }
+            continue
+        }
+        if let Some(expr_str) = line.strip_prefix("sizes ") {
+            let expr = match expr::lambda::parse(expr_str) {
+                Ok(x) => x,
+                Err(err) => {
+                    eprintln!("syntax error at {}", err.render(expr_str));
+                    continue
+                },
+            };
+            for &alg in Abstraction::all() {
+                match expr::turner::TurnerExpr::from_lambda(expr.clone(), alg) {
+                    Ok(e) => println!("{} {}", alg, e.size()),
+                    Err(err) => {
+                        eprintln!("error converting to SKI: {}", err);
+                        break
+                    },
+                }
+            }
+            continue
+        }
         let expr = match expr::lambda::parse(line) {
             Ok(x) => x,
             Err(err) => {
-                eprintln!("syntax error: {}", err);
+                eprintln!("syntax error at {}", err.render(line));
                 continue
             },
         };
         let expr_str = format!("{}", expr);
-        let iota = match expr::ski::SKIExpr::from_lambda(expr).map(expr::iota::IotaExpr::from) {
+        let expr_size = expr.size();
+        let combinators = match expr::turner::TurnerExpr::from_lambda(expr, algorithm) {
             Ok(x) => x,
             Err(err) => {
                 eprintln!("error converting to SKI: {}", err);
                 continue
             }
         };
+        let combinators_size = combinators.size();
+        let iota = expr::iota::IotaExpr::from(expr::ski::SKIExpr::from(combinators));
         println!("{} => {}", expr_str, iota.to_string());
+        eprintln!("size: lambda {}, {} {}, iota {}", expr_size, algorithm, combinators_size, iota.size());
     }
 }
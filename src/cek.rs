@@ -0,0 +1,479 @@
+use expr::debruijn::DbExpr;
+use expr::lambda::LambdaExpr;
+
+use std::fmt;
+use std::mem;
+use std::rc::Rc;
+
+/// Result of evaluating a term. Cloning and dropping use an explicit stack
+/// rather than recursion, so that deep values do not overflow the stack.
+pub enum Value<'a> {
+    /// Lambda term with the environment it was evaluated in.
+    Closure(&'a LambdaExpr, Env<'a>),
+    /// Free variable applied to values.
+    Neutral(&'a str, Vec<Value<'a>>),
+}
+
+/// Values of the variables in scope, innermost binding first.
+#[derive(Clone, Default)]
+pub struct Env<'a>(Option<Rc<Binding<'a>>>);
+
+impl<'a> Clone for Value<'a> {
+    fn clone(&self) -> Value<'a> {
+        // post-order: the arguments of a neutral value are built before it
+        enum Task<'v, 'a> {
+            Visit(&'v Value<'a>),
+            Neutral(&'a str, usize),
+        }
+        let mut tasks = vec![Task::Visit(self)];
+        let mut built = Vec::new();
+        while let Some(task) = tasks.pop() {
+            match task {
+                Task::Visit(Value::Closure(expr, env)) => built.push(Value::Closure(expr, env.clone())),
+                Task::Visit(Value::Neutral(head, args)) => {
+                    tasks.push(Task::Neutral(head, args.len()));
+                    tasks.extend(args.iter().rev().map(Task::Visit));
+                },
+                Task::Neutral(head, n) => {
+                    let args = built.split_off(built.len() - n);
+                    built.push(Value::Neutral(head, args));
+                },
+            }
+        }
+        built.pop().unwrap()
+    }
+}
+
+/// Drops the values and environments, and those nested in them, using an
+/// explicit stack. Environments still shared elsewhere are only released.
+fn release<'a>(mut values: Vec<Value<'a>>, mut envs: Vec<Env<'a>>) {
+    loop {
+        if let Some(mut value) = values.pop() {
+            match value {
+                Value::Closure(_, ref mut env) => envs.push(mem::take(env)),
+                Value::Neutral(_, ref mut args) => values.append(args),
+            }
+        } else if let Some(mut env) = envs.pop() {
+            if let Some(Ok(binding)) = env.0.take().map(Rc::try_unwrap) {
+                let Binding { value, next, .. } = binding;
+                values.push(value);
+                envs.push(next);
+            }
+        } else {
+            return;
+        }
+    }
+}
+
+impl<'a> Drop for Value<'a> {
+    fn drop(&mut self) {
+        match self {
+            Value::Closure(_, env) => release(Vec::new(), vec![mem::take(env)]),
+            Value::Neutral(_, args) => release(mem::take(args), Vec::new()),
+        }
+    }
+}
+
+impl<'a> Drop for Env<'a> {
+    fn drop(&mut self) {
+        if self.0.is_some() {
+            release(Vec::new(), vec![Env(self.0.take())]);
+        }
+    }
+}
+
+struct Binding<'a> {
+    name: &'a str,
+    value: Value<'a>,
+    next: Env<'a>,
+}
+
+impl<'a> Env<'a> {
+    fn extend(&self, name: &'a str, value: Value<'a>) -> Env<'a> {
+        Env(Some(Rc::new(Binding { name, value, next: self.clone() })))
+    }
+
+    pub fn lookup(&self, name: &str) -> Option<&Value<'a>> {
+        self.bindings().find(|&(n, _)| n == name).map(|(_, v)| v)
+    }
+
+    /// Every binding, innermost first, including those that are shadowed.
+    fn bindings(&self) -> impl Iterator<Item = (&'a str, &Value<'a>)> {
+        let mut env = self;
+        ::std::iter::from_fn(move || {
+            let binding = env.0.as_ref()?;
+            env = &binding.next;
+            Some((binding.name, &binding.value))
+        })
+    }
+
+    /// Writes the bindings that are not shadowed and for which `shown` holds.
+    fn write(&self, f: &mut fmt::Formatter, shown: &dyn Fn(&str) -> bool) -> Result<(), fmt::Error> {
+        let mut seen: Vec<&str> = Vec::new();
+        let mut first = true;
+        write!(f, "{{")?;
+        for (name, value) in self.bindings() {
+            if seen.contains(&name) {
+                continue;
+            }
+            seen.push(name);
+            if shown(name) {
+                write!(f, "{}{} ↦ {}", if first { "" } else { ", " }, name, value)?;
+                first = false;
+            }
+        }
+        write!(f, "}}")
+    }
+}
+
+/// What the machine does once the current term is a value.
+#[derive(Clone)]
+pub enum Frame<'a> {
+    /// Evaluate the argument of an application whose function is the value.
+    Arg(&'a LambdaExpr, Env<'a>),
+    /// Apply the function to the value.
+    Fun(Value<'a>),
+}
+
+#[derive(Clone)]
+pub enum Control<'a> {
+    Expr(&'a LambdaExpr),
+    Value(Value<'a>),
+}
+
+/// State of a CEK machine evaluating by call-by-value: control (the term or
+/// value at hand), environment and continuation (the frames still to be
+/// used, innermost last).
+#[derive(Clone)]
+pub struct State<'a> {
+    pub control: Control<'a>,
+    pub env: Env<'a>,
+    pub kont: Vec<Frame<'a>>,
+    /// Number of beta-reductions so far.
+    pub reductions: u32,
+}
+
+impl<'a> State<'a> {
+    pub fn new(expr: &'a LambdaExpr) -> State<'a> {
+        State { control: Control::Expr(expr), env: Env::default(), kont: Vec::new(), reductions: 0 }
+    }
+
+    /// Whether the control is a value and there is nothing left to do with it.
+    pub fn is_final(&self) -> bool {
+        matches!(self.control, Control::Value(_)) && self.kont.is_empty()
+    }
+
+    /// Makes one transition. Returns false if the state is final.
+    pub fn step(&mut self) -> bool {
+        if self.is_final() {
+            return false;
+        }
+        let control = mem::replace(&mut self.control, Control::Value(Value::Neutral("", Vec::new())));
+        self.control = match control {
+            Control::Expr(expr) => match expr {
+                LambdaExpr::Variable(v) => Control::Value(match self.env.lookup(v) {
+                    Some(value) => value.clone(),
+                    None => Value::Neutral(v, Vec::new()),
+                }),
+                LambdaExpr::Apply(e1, e2) => {
+                    self.kont.push(Frame::Arg(e2, self.env.clone()));
+                    Control::Expr(e1)
+                },
+                LambdaExpr::Lambda(_, _) => Control::Value(Value::Closure(expr, self.env.clone())),
+            },
+            Control::Value(value) => match self.kont.pop().unwrap() {
+                Frame::Arg(arg, env) => {
+                    self.kont.push(Frame::Fun(value));
+                    self.env = env;
+                    Control::Expr(arg)
+                },
+                Frame::Fun(mut function) => match function {
+                    Value::Closure(LambdaExpr::Lambda(v, body), ref env) => {
+                        self.reductions += 1;
+                        self.env = env.extend(v, value);
+                        Control::Expr(body)
+                    },
+                    Value::Neutral(head, ref mut args) => {
+                        let mut args = mem::take(args);
+                        args.push(value);
+                        Control::Value(Value::Neutral(head, args))
+                    },
+                    Value::Closure(_, _) => unreachable!(),
+                },
+            },
+        };
+        true
+    }
+
+    /// Whether the next transition is a beta-reduction.
+    fn reduces_next(&self) -> bool {
+        matches!((&self.control, self.kont.last()), (Control::Value(_), Some(Frame::Fun(Value::Closure(..)))))
+    }
+
+    /// Steps until the state is final, making at most `limit` more
+    /// beta-reductions. Returns false if the limit stopped it first.
+    pub fn run(&mut self, limit: u32) -> bool {
+        let mut steps = 0;
+        loop {
+            if self.reduces_next() {
+                if steps == limit {
+                    return false;
+                }
+                steps += 1;
+            }
+            if !self.step() {
+                return true;
+            }
+        }
+    }
+
+    /// The value of a final state, as a term.
+    pub fn result(&self) -> Option<LambdaExpr> {
+        match self.control {
+            Control::Value(ref value) if self.kont.is_empty() => Some(value.to_lambda()),
+            _ => None,
+        }
+    }
+}
+
+impl<'a> Value<'a> {
+    /// The term this value stands for, with the environment substituted.
+    pub fn to_lambda(&self) -> LambdaExpr {
+        LambdaExpr::from(self.to_db())
+    }
+
+    /// The value with the environments of its closures substituted.
+    /// Values have no free variables but those of the original term, so
+    /// they need no shifting.
+    fn to_db(&self) -> DbExpr {
+        enum Task<'v, 'a> {
+            Value(&'v Value<'a>),
+            /// Subterm of a closure inside the lambdas in `names`.
+            Expr(&'a LambdaExpr, &'v Env<'a>),
+            Apply,
+            Lambda,
+            /// Returns to the lambdas around a substituted value.
+            Restore(Vec<&'a str>),
+        }
+        // lambdas of the closure being substituted that enclose the
+        // subterm being visited, innermost last
+        let mut names: Vec<&str> = Vec::new();
+        let mut tasks = vec![Task::Value(self)];
+        let mut built = Vec::new();
+        while let Some(task) = tasks.pop() {
+            match task {
+                Task::Value(Value::Closure(expr, env)) => {
+                    tasks.push(Task::Restore(mem::take(&mut names)));
+                    tasks.push(Task::Expr(expr, env));
+                },
+                Task::Value(Value::Neutral(head, args)) => {
+                    built.push(DbExpr::Free(head.to_string()));
+                    for arg in args.iter().rev() {
+                        tasks.push(Task::Apply);
+                        tasks.push(Task::Value(arg));
+                    }
+                },
+                Task::Expr(LambdaExpr::Variable(v), env) => match names.iter().rev().position(|n| *n == v) {
+                    Some(i) => built.push(DbExpr::Bound(i)),
+                    None => match env.lookup(v) {
+                        Some(value) => tasks.push(Task::Value(value)),
+                        None => built.push(DbExpr::Free(v.clone())),
+                    },
+                },
+                Task::Expr(LambdaExpr::Apply(e1, e2), env) => {
+                    tasks.push(Task::Apply);
+                    tasks.push(Task::Expr(e2, env));
+                    tasks.push(Task::Expr(e1, env));
+                },
+                Task::Expr(LambdaExpr::Lambda(v, e), env) => {
+                    names.push(v);
+                    tasks.push(Task::Lambda);
+                    tasks.push(Task::Expr(e, env));
+                },
+                Task::Apply => {
+                    let e2 = built.pop().unwrap();
+                    let e1 = built.pop().unwrap();
+                    built.push(DbExpr::apply(e1, e2));
+                },
+                Task::Lambda => {
+                    let e = built.pop().unwrap();
+                    built.push(DbExpr::Lambda(names.pop().unwrap().to_string(), Box::new(e)));
+                },
+                Task::Restore(outer) => names = outer,
+            }
+        }
+        built.pop().unwrap()
+    }
+}
+
+/// Value of `expr` by call-by-value, i.e. evaluated function and argument
+/// first, never under a lambda, along with the number of beta-reductions
+/// performed; `None` if more than `limit` are needed.
+pub fn evaluate(expr: &LambdaExpr, limit: u32) -> Option<(LambdaExpr, u32)> {
+    let mut state = State::new(expr);
+    if !state.run(limit) {
+        return None;
+    }
+    Some((state.result().unwrap(), state.reductions))
+}
+
+impl<'a> fmt::Display for Value<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        match self {
+            Value::Closure(expr, env) => {
+                // only the variables the lambda uses
+                write!(f, "⟨{}, ", expr)?;
+                env.write(f, &|name| expr.contains(name))?;
+                write!(f, "⟩")
+            },
+            Value::Neutral(head, args) => {
+                write!(f, "{}", head)?;
+                for arg in args {
+                    match arg {
+                        Value::Neutral(_, args) if !args.is_empty() => write!(f, " ({})", arg)?,
+                        arg => write!(f, " {}", arg)?,
+                    }
+                }
+                Ok(())
+            },
+        }
+    }
+}
+
+impl<'a> fmt::Display for Env<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        self.write(f, &|_| true)
+    }
+}
+
+impl<'a> fmt::Display for Frame<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        match self {
+            Frame::Arg(expr, env) => {
+                write!(f, "arg({}, ", expr)?;
+                env.write(f, &|name| expr.contains(name))?;
+                write!(f, ")")
+            },
+            Frame::Fun(value) => write!(f, "fun({})", value),
+        }
+    }
+}
+
+impl<'a> fmt::Display for Control<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        match self {
+            Control::Expr(expr) => write!(f, "{}", expr),
+            Control::Value(value) => write!(f, "{}", value),
+        }
+    }
+}
+
+/// Three lines `C: ...`, `E: ...` and `K: ...`, the innermost frame first.
+impl<'a> fmt::Display for State<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        writeln!(f, "C: {}", self.control)?;
+        writeln!(f, "E: {}", self.env)?;
+        write!(f, "K: [")?;
+        for (i, frame) in self.kont.iter().rev().enumerate() {
+            write!(f, "{}{}", if i > 0 { ", " } else { "" }, frame)?;
+        }
+        write!(f, "]")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use cek::*;
+    use expr::lambda::parse;
+    use encoding::{numeral, Encoding};
+    use interpret::Strategy;
+    use krivine;
+    use prelude;
+
+    #[test]
+    fn evaluate_simple() {
+        assert_eq!(Some((parse("y").unwrap(), 1)), evaluate(&parse("(\\x x) y").unwrap(), 10));
+        // never under a lambda
+        assert_eq!(Some((parse("\\y (\\x x) y").unwrap(), 0)), evaluate(&parse("\\y (\\x x) y").unwrap(), 10));
+        // the environment is substituted into the result
+        assert_eq!(Some((parse("\\y z").unwrap(), 1)), evaluate(&parse("(\\x \\y x) z").unwrap(), 10));
+        assert_eq!(Some((parse("\\y1 y").unwrap(), 1)), evaluate(&parse("(\\x \\y x) y").unwrap(), 10));
+        assert_eq!(Some((parse("f (g z) z").unwrap(), 2)), evaluate(&parse("(\\x f x z) ((\\y g y) z)").unwrap(), 10));
+    }
+
+    #[test]
+    fn evaluate_strict() {
+        // the argument is evaluated even though it is unused
+        assert_eq!(None, evaluate(&parse("(\\x y) ((\\x x x) (\\x x x))").unwrap(), 100));
+        let e = prelude::parse("mul (exp 2 3) (pred 4)").unwrap();
+        let (e, _) = evaluate(&e, 10000).unwrap();
+        assert!(krivine::normalize(&e, 10000).unwrap().0.alpha_eq(&numeral(24, Encoding::Church)));
+    }
+
+    #[test]
+    fn evaluate_matches_cbv() {
+        for s in &["(\\x \\y y x) ((\\z z) a) ((\\z z) b)", "(\\f f (f a)) ((\\g g) (\\x x))", "Z (\\f \\n n) (\\x x)"] {
+            let e = prelude::parse(s).unwrap();
+            let (expected, steps) = e.clone().repeated_beta_with(Strategy::CallByValue, 1000);
+            let (result, cek_steps) = evaluate(&e, 1000).unwrap();
+            assert!(result.alpha_eq(&expected), "{}: {} vs {}", s, result, expected);
+            assert_eq!(steps, cek_steps, "{}", s);
+        }
+    }
+
+    #[test]
+    fn evaluate_limit() {
+        let e = parse("(\\x x) ((\\x x) y)").unwrap();
+        assert_eq!(Some((parse("y").unwrap(), 2)), evaluate(&e, 2));
+        assert_eq!(None, evaluate(&e, 1));
+        // the step that would exceed the limit is not taken
+        let mut state = State::new(&e);
+        assert!(!state.run(1));
+        assert_eq!(1, state.reductions);
+        assert!(state.run(1));
+        assert_eq!(2, state.reductions);
+        assert_eq!(Some(parse("y").unwrap()), state.result());
+    }
+
+    #[test]
+    fn deep_values() {
+        // deeper than the recursion the default test thread stack allows
+        let n = 300000;
+        let deep = numeral(n, Encoding::Church);
+        assert_eq!(Some((deep.clone(), 0)), evaluate(&deep, 10));
+        let spine = (0..n).fold(parse("x").unwrap(), |e, _| LambdaExpr::apply(parse("f").unwrap(), e));
+        assert_eq!(Some((spine.clone(), 0)), evaluate(&spine, 10));
+        // a deep value looked up, and one in the environment of a closure
+        let e = LambdaExpr::apply(parse("\\v v").unwrap(), spine.clone());
+        assert_eq!(Some((spine.clone(), 1)), evaluate(&e, 10));
+        let e = LambdaExpr::apply(parse("\\v \\z v").unwrap(), spine.clone());
+        assert_eq!(Some((LambdaExpr::Lambda("z".to_string(), Box::new(spine)), 1)), evaluate(&e, 10));
+    }
+
+    #[test]
+    fn states() {
+        let e = parse("(\\x \\y x) z").unwrap();
+        let mut state = State::new(&e);
+        let mut shown = vec![state.to_string()];
+        while state.step() {
+            shown.push(state.to_string());
+        }
+        assert_eq!(vec![
+            "C: (λx λy x) z\nE: {}\nK: []",
+            "C: λx λy x\nE: {}\nK: [arg(z, {})]",
+            "C: ⟨λx λy x, {}⟩\nE: {}\nK: [arg(z, {})]",
+            "C: z\nE: {}\nK: [fun(⟨λx λy x, {}⟩)]",
+            "C: z\nE: {}\nK: [fun(⟨λx λy x, {}⟩)]",
+            "C: λy x\nE: {x ↦ z}\nK: []",
+            "C: ⟨λy x, {x ↦ z}⟩\nE: {x ↦ z}\nK: []",
+        ], shown);
+        assert!(state.is_final());
+        assert_eq!(1, state.reductions);
+        assert_eq!(Some(parse("\\y z").unwrap()), state.result());
+        // shadowed bindings and, in closures, unused ones are hidden
+        let e = parse("(\\x \\y \\x \\z y) a b c").unwrap();
+        let mut state = State::new(&e);
+        while state.step() {}
+        assert_eq!("C: ⟨λz y, {y ↦ b}⟩\nE: {x ↦ c, y ↦ b}\nK: []", state.to_string());
+    }
+}
@@ -1,14 +1,19 @@
-use expr::lambda::LambdaExpr;
+use expr::lambda::{LambdaExpr, Node};
 use expr::ski::SKIExpr;
 use expr::iota::IotaExpr;
+use expr::debruijn::DbExpr;
+use expr::turner::TurnerExpr;
+use expr::systemf::FExpr;
+use expr::combinator::Combinator;
 
 use std::fmt;
+use std::mem;
+use std::str::FromStr;
 
 impl From<SKIExpr> for LambdaExpr {
     fn from(expr: SKIExpr) -> LambdaExpr {
-        match expr {
-            SKIExpr::Apply(e1, e2) =>
-                LambdaExpr::Apply(Box::new(LambdaExpr::from(*e1)), Box::new(LambdaExpr::from(*e2))),
+        expr.fold(|e| match e {
+            SKIExpr::Apply(..) => unreachable!(),
             SKIExpr::S =>
                 LambdaExpr::Lambda("x".to_string(),
                     Box::new(LambdaExpr::Lambda("y".to_string(),
@@ -27,118 +32,752 @@ impl From<SKIExpr> for LambdaExpr {
             SKIExpr::I =>
                 LambdaExpr::Lambda("x".to_string(),
                     Box::new(LambdaExpr::Variable("x".to_string()))),
-        }
+        }, LambdaExpr::apply)
     }
 }
 
+/// Intermediate term of the translations, with lambdas and combinators.
+/// Dropping uses an explicit stack rather than recursion.
 #[derive(Debug)]
 enum IntExpr {
     Variable(String),
     Apply(Box<IntExpr>, Box<IntExpr>),
     Lambda(String, Box<IntExpr>),
     S, K, I,
+    B, C, SPrime, BStar, CPrime,
+    BulkB(usize), BulkC(usize), BulkS(usize),
+}
+
+/// Algorithm used to eliminate lambdas.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Abstraction {
+    /// S, K and I only, with the eta shortcut; same as `SKIExpr::from_lambda`.
+    Basic,
+    /// Adds B and C, using Curry's optimizations.
+    Curry,
+    /// Adds B, C, S', B* and C', using Turner's optimizations.
+    Turner,
+    /// Kiselyov's compositional translation over de Bruijn indices, using B and C.
+    Kiselyov,
+    /// Kiselyov's translation, tracking which variables are used to avoid
+    /// K chains and eta-reducing `λx f x`.
+    KiselyovEta,
+    /// Kiselyov's translation with bulk combinators, also used to reach a
+    /// variable under any number of binders, so the output is linear in the
+    /// size of the input.
+    KiselyovBulk,
+}
+
+impl Abstraction {
+    pub fn all() -> &'static [Abstraction] {
+        &[Abstraction::Basic, Abstraction::Curry, Abstraction::Turner,
+          Abstraction::Kiselyov, Abstraction::KiselyovEta, Abstraction::KiselyovBulk]
+    }
+
+    pub fn name(&self) -> &'static str {
+        match *self {
+            Abstraction::Basic => "basic",
+            Abstraction::Curry => "curry",
+            Abstraction::Turner => "turner",
+            Abstraction::Kiselyov => "kiselyov",
+            Abstraction::KiselyovEta => "kiselyov-eta",
+            Abstraction::KiselyovBulk => "kiselyov-bulk",
+        }
+    }
+}
+
+impl fmt::Display for Abstraction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        write!(f, "{}", self.name())
+    }
+}
+
+impl FromStr for Abstraction {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Abstraction, String> {
+        Abstraction::all().iter().cloned().find(|alg| alg.name() == s)
+            .ok_or_else(|| format!("unknown algorithm: {}", s))
+    }
 }
 
 
 impl From<LambdaExpr> for IntExpr {
     fn from(expr: LambdaExpr) -> IntExpr {
-        match expr {
-            LambdaExpr::Variable(s) => IntExpr::Variable(s),
-            LambdaExpr::Apply(e1, e2) => IntExpr::Apply(Box::new(IntExpr::from(*e1)), Box::new(IntExpr::from(*e2))),
-            LambdaExpr::Lambda(v, e) => IntExpr::Lambda(v, Box::new(IntExpr::from(*e))),
+        enum Task {
+            Visit(LambdaExpr),
+            Apply,
+            Lambda(String),
+        }
+        let mut tasks = vec![Task::Visit(expr)];
+        let mut built = Vec::new();
+        while let Some(task) = tasks.pop() {
+            match task {
+                Task::Visit(e) => match e.into_node() {
+                    Node::Variable(s) => built.push(IntExpr::Variable(s)),
+                    Node::Apply(e1, e2) => {
+                        tasks.push(Task::Apply);
+                        tasks.push(Task::Visit(e2));
+                        tasks.push(Task::Visit(e1));
+                    },
+                    Node::Lambda(v, e) => {
+                        tasks.push(Task::Lambda(v));
+                        tasks.push(Task::Visit(e));
+                    },
+                },
+                Task::Apply => {
+                    let e2 = built.pop().unwrap();
+                    let e1 = built.pop().unwrap();
+                    built.push(apply(e1, e2));
+                },
+                Task::Lambda(v) => {
+                    let e = built.pop().unwrap();
+                    built.push(IntExpr::Lambda(v, Box::new(e)));
+                },
+            }
         }
+        built.pop().unwrap()
     }
 }
 
 impl From<SKIExpr> for IntExpr {
     fn from(expr: SKIExpr) -> IntExpr {
-        match expr {
-            SKIExpr::Apply(e1, e2) => IntExpr::Apply(Box::new(IntExpr::from(*e1)), Box::new(IntExpr::from(*e2))),
+        expr.fold(|e| match e {
+            SKIExpr::Apply(..) => unreachable!(),
             SKIExpr::S => IntExpr::S,
             SKIExpr::K => IntExpr::K,
             SKIExpr::I => IntExpr::I,
+        }, apply)
+    }
+}
+
+impl From<TurnerExpr> for IntExpr {
+    fn from(expr: TurnerExpr) -> IntExpr {
+        expr.fold(|e| match *e {
+            TurnerExpr::Apply(..) => unreachable!(),
+            TurnerExpr::S => IntExpr::S,
+            TurnerExpr::K => IntExpr::K,
+            TurnerExpr::I => IntExpr::I,
+            TurnerExpr::B => IntExpr::B,
+            TurnerExpr::C => IntExpr::C,
+            TurnerExpr::SPrime => IntExpr::SPrime,
+            TurnerExpr::BStar => IntExpr::BStar,
+            TurnerExpr::CPrime => IntExpr::CPrime,
+            TurnerExpr::BulkB(n) => IntExpr::BulkB(n),
+            TurnerExpr::BulkC(n) => IntExpr::BulkC(n),
+            TurnerExpr::BulkS(n) => IntExpr::BulkS(n),
+        }, apply)
+    }
+}
+
+fn apply(e1: IntExpr, e2: IntExpr) -> IntExpr {
+    IntExpr::Apply(Box::new(e1), Box::new(e2))
+}
+
+impl Drop for IntExpr {
+    fn drop(&mut self) {
+        let mut stack = Vec::new();
+        self.take_children(&mut stack);
+        while let Some(mut e) = stack.pop() {
+            e.take_children(&mut stack);
         }
     }
 }
 
 impl IntExpr {
-    fn contains(&self, var: &str) -> bool {
+    /// Moves the term out, leaving `I` in its place.
+    fn take(&mut self) -> IntExpr {
+        mem::replace(self, IntExpr::I)
+    }
+
+    /// Moves the children of the term onto `stack`, leaving placeholders.
+    fn take_children(&mut self, stack: &mut Vec<IntExpr>) {
         match self {
-            &IntExpr::Variable(ref v) => v == var,
-            &IntExpr::Apply(ref e1, ref e2) => e1.contains(var) || e2.contains(var),
-            &IntExpr::Lambda(ref v, ref e) => v != var && e.contains(var),
-            _ => false
+            IntExpr::Apply(e1, e2) => {
+                stack.push(e1.take());
+                stack.push(e2.take());
+            },
+            IntExpr::Lambda(_, e) => stack.push(e.take()),
+            _ => {},
         }
     }
 
-    fn is_var(&self, var: &str) -> bool {
-        if let &IntExpr::Variable(ref v) = self {
-            v == var
-        } else { false }
+    /// Eliminates all lambdas, innermost first.
+    fn compile(self, algorithm: Abstraction) -> IntExpr {
+        enum Task {
+            Visit(IntExpr),
+            Apply,
+            Abstract(String),
+        }
+        let mut tasks = vec![Task::Visit(self)];
+        let mut built = Vec::new();
+        while let Some(task) = tasks.pop() {
+            match task {
+                Task::Visit(mut e) => match e {
+                    IntExpr::Apply(ref mut e1, ref mut e2) => {
+                        tasks.push(Task::Apply);
+                        tasks.push(Task::Visit(e2.take()));
+                        tasks.push(Task::Visit(e1.take()));
+                    },
+                    IntExpr::Lambda(ref mut v, ref mut body) => {
+                        tasks.push(Task::Abstract(mem::take(v)));
+                        tasks.push(Task::Visit(body.take()));
+                    },
+                    _ => built.push(e),
+                },
+                Task::Apply => {
+                    let e2 = built.pop().unwrap();
+                    let e1 = built.pop().unwrap();
+                    built.push(apply(e1, e2));
+                },
+                Task::Abstract(v) => {
+                    let e = built.pop().unwrap();
+                    built.push(e.abstract_var(&v, algorithm));
+                },
+            }
+        }
+        built.pop().unwrap()
     }
 
-    fn translate(self) -> IntExpr {
-        match self {
-            IntExpr::Apply(e1, e2) => IntExpr::Apply(Box::new(IntExpr::translate(*e1)), Box::new(IntExpr::translate(*e2))),
-            IntExpr::Lambda(v, e) => if !e.contains(&v) {
-                    IntExpr::Apply(Box::new(IntExpr::K), Box::new(IntExpr::translate(*e)))
-                } else {
-                    let (e,) = (*e,); // see #16223
-                    match e {
-                        IntExpr::Variable(v2) => {
-                                assert_eq!(v, v2); // otherwise e.contains(v) must be false
-                                IntExpr::I
-                            },
-                        IntExpr::Apply(e1, e2) =>
-                            if e2.is_var(&v) && !e1.contains(&v) {
-                                e1.translate()
-                            } else {
-                                IntExpr::Apply(
-                                    Box::new(IntExpr::Apply(
-                                        Box::new(IntExpr::S),
-                                        Box::new(IntExpr::translate(
-                                            IntExpr::Lambda(v.clone(), e1))))),
-                                    Box::new(IntExpr::translate(
-                                            IntExpr::Lambda(v, e2))))
-                            },
-                        e @ IntExpr::Lambda(_, _) =>
-                                IntExpr::translate(
-                                    IntExpr::Lambda(v,
-                                        Box::new(IntExpr::from(IntExpr::translate(e))))),
-                        _ => unreachable!() // (S, K, I).contains(_) == false
-                    }
+    /// Abstracts `var` out of a term that contains no lambdas, eta-reducing
+    /// `λx p x` to `p` and building the rest with `combine`.
+    fn abstract_var(self, var: &str, algorithm: Abstraction) -> IntExpr {
+        enum Part {
+            /// Subterm not containing `var`.
+            Free(IntExpr),
+            /// `var` itself.
+            Var,
+            /// Abstraction of a subterm containing `var`.
+            Bound(IntExpr),
+        }
+        impl Part {
+            fn abstraction(self) -> IntExpr {
+                match self {
+                    Part::Free(e) => apply(IntExpr::K, e),
+                    Part::Var => IntExpr::I,
+                    Part::Bound(e) => e,
+                }
+            }
+        }
+        enum Task {
+            Visit(IntExpr),
+            Apply,
+        }
+        let mut tasks = vec![Task::Visit(self)];
+        let mut parts = Vec::new();
+        while let Some(task) = tasks.pop() {
+            match task {
+                Task::Visit(mut e) => match e {
+                    IntExpr::Apply(ref mut e1, ref mut e2) => {
+                        tasks.push(Task::Apply);
+                        tasks.push(Task::Visit(e2.take()));
+                        tasks.push(Task::Visit(e1.take()));
+                    },
+                    IntExpr::Variable(ref v) if v == var => parts.push(Part::Var),
+                    _ => parts.push(Part::Free(e)),
                 },
-            e => e
+                Task::Apply => {
+                    let q = parts.pop().unwrap();
+                    let p = parts.pop().unwrap();
+                    parts.push(match (p, q) {
+                        (Part::Free(p), Part::Free(q)) => Part::Free(apply(p, q)),
+                        (Part::Free(p), Part::Var) => Part::Bound(p),
+                        (p, q) => Part::Bound(IntExpr::combine(p.abstraction(), q.abstraction(), algorithm)),
+                    });
+                },
+            }
         }
+        parts.pop().unwrap().abstraction()
     }
 
-    fn display_lambda(&self) -> LambdaExpr {
+    fn is_i(&self) -> bool {
+        matches!(*self, IntExpr::I)
+    }
+
+    /// Whether this term is `K p`.
+    fn is_k_app(&self) -> bool {
+        if let IntExpr::Apply(e1, _) = self {
+            if let IntExpr::K = **e1 { return true; }
+        }
+        false
+    }
+
+    /// Whether this term is `B p q`.
+    fn is_b_app(&self) -> bool {
+        if let IntExpr::Apply(e1, _) = self {
+            if let IntExpr::Apply(ref e1, _) = **e1 {
+                if let IntExpr::B = **e1 { return true; }
+            }
+        }
+        false
+    }
+
+    fn unapply(mut self) -> (IntExpr, IntExpr) {
         match self {
-            &IntExpr::Variable(ref v) => LambdaExpr::Variable(v.clone()),
-            &IntExpr::Apply(ref e1, ref e2) => LambdaExpr::Apply(Box::new(e1.display_lambda()), Box::new(e2.display_lambda())),
-            &IntExpr::Lambda(ref v, ref e) => LambdaExpr::Lambda(v.clone(), Box::new(e.display_lambda())),
-            &IntExpr::S => LambdaExpr::Variable("S".to_string()),
-            &IntExpr::K => LambdaExpr::Variable("K".to_string()),
-            &IntExpr::I => LambdaExpr::Variable("I".to_string()),
+            IntExpr::Apply(ref mut e1, ref mut e2) => (e1.take(), e2.take()),
+            _ => unreachable!(),
+        }
+    }
+
+    /// Builds the abstraction of an application whose parts abstract
+    /// to `p` and `q`, i.e. an optimized `S p q`.
+    fn combine(p: IntExpr, q: IntExpr, algorithm: Abstraction) -> IntExpr {
+        if algorithm == Abstraction::Basic {
+            return apply(apply(IntExpr::S, p), q);
+        }
+        if p.is_k_app() {
+            let (_, p) = p.unapply();
+            if q.is_k_app() {
+                // S (K p) (K q) = K (p q)
+                let (_, q) = q.unapply();
+                return apply(IntExpr::K, apply(p, q));
+            }
+            if q.is_i() {
+                // S (K p) I = p
+                return p;
+            }
+            if algorithm == Abstraction::Turner && q.is_b_app() {
+                // S (K p) (B q r) = B* p q r
+                let (bq, r) = q.unapply();
+                let (_, q) = bq.unapply();
+                return apply(apply(apply(IntExpr::BStar, p), q), r);
+            }
+            // S (K p) q = B p q
+            return apply(apply(IntExpr::B, p), q);
+        }
+        if q.is_k_app() {
+            let (_, q) = q.unapply();
+            if algorithm == Abstraction::Turner && p.is_b_app() {
+                // S (B p q) (K r) = C' p q r
+                let (bp, pq) = p.unapply();
+                let (_, p) = bp.unapply();
+                return apply(apply(apply(IntExpr::CPrime, p), pq), q);
+            }
+            // S p (K q) = C p q
+            return apply(apply(IntExpr::C, p), q);
+        }
+        if algorithm == Abstraction::Turner && p.is_b_app() {
+            // S (B p q) r = S' p q r
+            let (bp, pq) = p.unapply();
+            let (_, p) = bp.unapply();
+            return apply(apply(apply(IntExpr::SPrime, p), pq), q);
         }
+        apply(apply(IntExpr::S, p), q)
     }
+
+    /// Variable or combinator name, or `None` for applications and lambdas.
+    fn name(&self) -> Option<String> {
+        Some(match self {
+            IntExpr::Apply(..) | IntExpr::Lambda(..) => return None,
+            IntExpr::Variable(v) => v.clone(),
+            IntExpr::S => "S".to_string(),
+            IntExpr::K => "K".to_string(),
+            IntExpr::I => "I".to_string(),
+            IntExpr::B => "B".to_string(),
+            IntExpr::C => "C".to_string(),
+            IntExpr::SPrime => "S'".to_string(),
+            IntExpr::BStar => "B*".to_string(),
+            IntExpr::CPrime => "C'".to_string(),
+            IntExpr::BulkB(n) => format!("B{}", n),
+            IntExpr::BulkC(n) => format!("C{}", n),
+            IntExpr::BulkS(n) => format!("S{}", n),
+        })
+    }
+
+    fn display_lambda(&self) -> LambdaExpr {
+        enum Task<'a> {
+            Visit(&'a IntExpr),
+            Apply,
+            Lambda(&'a str),
+        }
+        let mut tasks = vec![Task::Visit(self)];
+        let mut built = Vec::new();
+        while let Some(task) = tasks.pop() {
+            match task {
+                Task::Visit(IntExpr::Apply(e1, e2)) => {
+                    tasks.push(Task::Apply);
+                    tasks.push(Task::Visit(e2));
+                    tasks.push(Task::Visit(e1));
+                },
+                Task::Visit(IntExpr::Lambda(v, e)) => {
+                    tasks.push(Task::Lambda(v));
+                    tasks.push(Task::Visit(e));
+                },
+                Task::Visit(e) => built.push(LambdaExpr::Variable(e.name().unwrap())),
+                Task::Apply => {
+                    let e2 = built.pop().unwrap();
+                    let e1 = built.pop().unwrap();
+                    built.push(LambdaExpr::apply(e1, e2));
+                },
+                Task::Lambda(v) => {
+                    let e = built.pop().unwrap();
+                    built.push(LambdaExpr::Lambda(v.to_string(), Box::new(e)));
+                },
+            }
+        }
+        built.pop().unwrap()
+    }
+}
+
+/// Combinator term with the same shape as `expr`, mapping each combinator
+/// with `leaf`, which returns `None` for those outside the target set.
+fn from_int<E: Combinator>(expr: IntExpr, leaf: impl Fn(&IntExpr) -> Option<E>) -> Result<E, String> {
+    enum Task {
+        Visit(IntExpr),
+        Apply,
+    }
+    let mut tasks = vec![Task::Visit(expr)];
+    let mut built = Vec::new();
+    while let Some(task) = tasks.pop() {
+        match task {
+            Task::Visit(mut e) => match e {
+                IntExpr::Apply(ref mut e1, ref mut e2) => {
+                    tasks.push(Task::Apply);
+                    tasks.push(Task::Visit(e2.take()));
+                    tasks.push(Task::Visit(e1.take()));
+                },
+                IntExpr::Variable(ref v) => return Err(format!("free variable: {}", v)),
+                IntExpr::Lambda(..) => return Err(format!("untranslated lambda: {}", e)),
+                _ => match leaf(&e) {
+                    Some(c) => built.push(c),
+                    None => return Err(format!("not an SKI combinator: {}", e)),
+                },
+            },
+            Task::Apply => {
+                let e2 = built.pop().unwrap();
+                let e1 = built.pop().unwrap();
+                built.push(E::join(e1, e2));
+            },
+        }
+    }
+    Ok(built.pop().unwrap())
 }
 
 impl SKIExpr {
     pub fn from_lambda(expr: LambdaExpr) -> Result<SKIExpr, String> {
-        SKIExpr::from_int(IntExpr::from(expr).translate())
+        SKIExpr::from_int(IntExpr::from(expr).compile(Abstraction::Basic))
     }
 
     fn from_int(expr: IntExpr) -> Result<SKIExpr, String> {
-        match expr {
-            IntExpr::Apply(e1, e2) => Ok(SKIExpr::Apply(Box::new(SKIExpr::from_int(*e1)?), Box::new(SKIExpr::from_int(*e2)?))),
-            IntExpr::S => Ok(SKIExpr::S),
-            IntExpr::K => Ok(SKIExpr::K),
-            IntExpr::I => Ok(SKIExpr::I),
-            IntExpr::Variable(v) => Err(format!("free variable: {}", v)),
-            l @ IntExpr::Lambda(_, _) => Err(format!("untranslated lambda: {}", l)),
+        from_int(expr, |e| match *e {
+            IntExpr::S => Some(SKIExpr::S),
+            IntExpr::K => Some(SKIExpr::K),
+            IntExpr::I => Some(SKIExpr::I),
+            _ => None,
+        })
+    }
+}
+
+// Kiselyov, "λ to SKI, semantically" (FLOPS 2018).
+//
+// A term under n binders translates to a pair (n, d) where d applied to
+// the n innermost variables (outermost first) gives the term; 0 means the
+// term is closed.
+
+fn bulk(n: usize, one: IntExpr, many: fn(usize) -> IntExpr) -> IntExpr {
+    if n == 1 { one } else { many(n) }
+}
+
+/// `C C x` (flipped application) applied to `y` is just `C y x`.
+fn apply_flipped(e1: IntExpr, e2: IntExpr) -> IntExpr {
+    if let IntExpr::Apply(ref f, _) = e1 {
+        if let IntExpr::Apply(ref c1, ref c2) = **f {
+            if let (IntExpr::C, IntExpr::C) = (&**c1, &**c2) {
+                let (_, x) = e1.unapply();
+                // C (C C) x = C x
+                if let IntExpr::Apply(ref c1, ref c2) = e2 {
+                    if let (IntExpr::C, IntExpr::C) = (&**c1, &**c2) {
+                        return apply(IntExpr::C, x);
+                    }
+                }
+                return apply(apply(IntExpr::C, e2), x);
+            }
         }
     }
+    apply(e1, e2)
+}
+
+fn kiselyov_combine(mut t1: (usize, IntExpr), mut t2: (usize, IntExpr), algorithm: Abstraction) -> IntExpr {
+    let r = || apply(IntExpr::C, IntExpr::C);
+    loop {
+        let (next1, next2) = match (t1, t2, algorithm) {
+            ((0, d1), (0, d2), _) => return apply_flipped(d1, d2),
+            ((0, d1), (n, d2), Abstraction::KiselyovBulk) =>
+                return apply(apply(bulk(n, IntExpr::B, IntExpr::BulkB), d1), d2),
+            ((n, d1), (0, d2), Abstraction::KiselyovBulk) =>
+                return apply(apply(bulk(n, IntExpr::C, IntExpr::BulkC), d1), d2),
+            ((n1, d1), (n2, d2), Abstraction::KiselyovBulk) => return if n1 == n2 {
+                apply(apply(bulk(n1, IntExpr::S, IntExpr::BulkS), d1), d2)
+            } else if n1 < n2 {
+                let s = apply(bulk(n1, IntExpr::S, IntExpr::BulkS), d1);
+                apply(apply(bulk(n2 - n1, IntExpr::B, IntExpr::BulkB), s), d2)
+            } else {
+                let k = n1 - n2;
+                let b = apply(apply(bulk(k, IntExpr::B, IntExpr::BulkB), bulk(n2, IntExpr::S, IntExpr::BulkS)), d1);
+                apply(apply(bulk(k, IntExpr::C, IntExpr::BulkC), b), d2)
+            },
+            ((0, d1), (n, d2), _) => ((0, apply(IntExpr::B, d1)), (n - 1, d2)),
+            ((n, d1), (0, d2), _) => ((0, apply(r(), d2)), (n - 1, d1)),
+            ((n1, d1), (n2, d2), _) => {
+                // only combines with closed terms, so does not recurse further
+                let s = kiselyov_combine((0, IntExpr::S), (n1 - 1, d1), algorithm);
+                ((n1 - 1, s), (n2 - 1, d2))
+            },
+        };
+        t1 = next1;
+        t2 = next2;
+    }
+}
+
+/// Translation of the variable with de Bruijn index `index`. Bulk
+/// combinators drop the `index` inner variables at once with
+/// `Ki x y1..yi = x`, i.e. `Ki = C (B Bi K) I` since `Bi (K x) I y1..yi = x`;
+/// the others apply K once per variable.
+fn kiselyov_var(index: usize, algorithm: Abstraction) -> (usize, IntExpr) {
+    if algorithm == Abstraction::KiselyovBulk {
+        return match index {
+            0 => (1, IntExpr::I),
+            1 => (2, IntExpr::K),
+            i => (i + 1, apply(apply(IntExpr::C, apply(apply(IntExpr::B, IntExpr::BulkB(i)), IntExpr::K)), IntExpr::I)),
+        };
+    }
+    let mut t = (1, IntExpr::I);
+    for i in 1..=index {
+        t = (i + 1, kiselyov_combine((0, IntExpr::K), t, algorithm));
+    }
+    t
+}
+
+fn kiselyov(expr: &DbExpr, algorithm: Abstraction) -> Result<(usize, IntExpr), String> {
+    enum Task<'a> {
+        Visit(&'a DbExpr),
+        Apply,
+        Lambda,
+    }
+    let mut tasks = vec![Task::Visit(expr)];
+    let mut built = Vec::new();
+    while let Some(task) = tasks.pop() {
+        match task {
+            Task::Visit(DbExpr::Free(v)) => return Err(format!("free variable: {}", v)),
+            Task::Visit(DbExpr::Bound(i)) => built.push(kiselyov_var(*i, algorithm)),
+            Task::Visit(DbExpr::Apply(e1, e2)) => {
+                tasks.push(Task::Apply);
+                tasks.push(Task::Visit(e2));
+                tasks.push(Task::Visit(e1));
+            },
+            Task::Visit(DbExpr::Lambda(_, e)) => {
+                tasks.push(Task::Lambda);
+                tasks.push(Task::Visit(e));
+            },
+            Task::Apply => {
+                let t2 = built.pop().unwrap();
+                let t1 = built.pop().unwrap();
+                let n = t1.0.max(t2.0);
+                built.push((n, kiselyov_combine(t1, t2, algorithm)));
+            },
+            Task::Lambda => {
+                let t = match built.pop().unwrap() {
+                    (0, d) => (0, apply(IntExpr::K, d)),
+                    (n, d) => (n - 1, d),
+                };
+                built.push(t);
+            },
+        }
+    }
+    Ok(built.pop().unwrap())
+}
+
+/// Whether a term in the eta-optimized variant uses a variable.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Use {
+    Need,
+    Weak,
+}
+
+/// Translation of a term in the eta-optimized variant: `term` applied to the
+/// variables marked `Need` in `uses`, outermost first, gives the term.
+/// `uses` has the innermost variable last and stops at the outermost one the
+/// term needs, so it is empty for a closed term.
+struct Sem {
+    term: IntExpr,
+    uses: Vec<Use>,
+}
+
+fn closed(term: IntExpr) -> Sem {
+    Sem { term, uses: Vec::new() }
+}
+
+fn sem_combine(s1: Sem, s2: Sem) -> Sem {
+    enum Task {
+        Combine(Sem, Sem),
+        /// Combines the last result with this.
+        CombineWith(Sem),
+        /// Marks the innermost variable of the last result.
+        Mark(Use),
+    }
+    let mut tasks = vec![Task::Combine(s1, s2)];
+    let mut built = Vec::new();
+    while let Some(task) = tasks.pop() {
+        match task {
+            Task::Combine(mut s1, mut s2) => match (s1.uses.pop(), s2.uses.pop()) {
+                (None, None) => built.push(closed(apply_flipped(s1.term, s2.term))),
+                (None, Some(Use::Need)) => if s2.uses.is_empty() && s2.term.is_i() {
+                    // λx d x = d
+                    s1.uses.push(Use::Need);
+                    built.push(s1);
+                } else {
+                    tasks.push(Task::Mark(Use::Need));
+                    tasks.push(Task::Combine(closed(apply(IntExpr::B, s1.term)), s2));
+                },
+                (None, Some(Use::Weak)) | (Some(Use::Weak), None) | (Some(Use::Weak), Some(Use::Weak)) => {
+                    tasks.push(Task::Mark(Use::Weak));
+                    tasks.push(Task::Combine(s1, s2));
+                },
+                (Some(Use::Need), None) => {
+                    tasks.push(Task::Mark(Use::Need));
+                    tasks.push(Task::Combine(closed(apply(apply(IntExpr::C, IntExpr::C), s2.term)), s1));
+                },
+                (Some(Use::Need), Some(Use::Need)) => {
+                    tasks.push(Task::Mark(Use::Need));
+                    tasks.push(Task::CombineWith(s2));
+                    tasks.push(Task::Combine(closed(IntExpr::S), s1));
+                },
+                (Some(Use::Need), Some(Use::Weak)) => {
+                    tasks.push(Task::Mark(Use::Need));
+                    tasks.push(Task::CombineWith(s1));
+                    tasks.push(Task::Combine(closed(apply(IntExpr::C, IntExpr::C)), s2));
+                },
+                (Some(Use::Weak), Some(Use::Need)) => {
+                    tasks.push(Task::Mark(Use::Need));
+                    tasks.push(Task::CombineWith(s2));
+                    tasks.push(Task::Combine(closed(IntExpr::B), s1));
+                },
+            },
+            Task::CombineWith(s2) => {
+                let s1 = built.pop().unwrap();
+                tasks.push(Task::Combine(s1, s2));
+            },
+            Task::Mark(u) => built.last_mut().unwrap().uses.push(u),
+        }
+    }
+    built.pop().unwrap()
+}
+
+fn kiselyov_eta(expr: &DbExpr) -> Result<Sem, String> {
+    enum Task<'a> {
+        Visit(&'a DbExpr),
+        Apply,
+        Lambda,
+    }
+    let mut tasks = vec![Task::Visit(expr)];
+    let mut built = Vec::new();
+    while let Some(task) = tasks.pop() {
+        match task {
+            Task::Visit(DbExpr::Free(v)) => return Err(format!("free variable: {}", v)),
+            Task::Visit(DbExpr::Bound(i)) => {
+                let mut uses = vec![Use::Need];
+                uses.resize(i + 1, Use::Weak);
+                built.push(Sem { term: IntExpr::I, uses });
+            },
+            Task::Visit(DbExpr::Apply(e1, e2)) => {
+                tasks.push(Task::Apply);
+                tasks.push(Task::Visit(e2));
+                tasks.push(Task::Visit(e1));
+            },
+            Task::Visit(DbExpr::Lambda(_, e)) => {
+                tasks.push(Task::Lambda);
+                tasks.push(Task::Visit(e));
+            },
+            Task::Apply => {
+                let s2 = built.pop().unwrap();
+                let s1 = built.pop().unwrap();
+                built.push(sem_combine(s1, s2));
+            },
+            Task::Lambda => {
+                let mut s = built.pop().unwrap();
+                let s = match s.uses.pop() {
+                    None => closed(apply(IntExpr::K, s.term)),
+                    Some(Use::Need) => s,
+                    Some(Use::Weak) => sem_combine(closed(IntExpr::K), s),
+                };
+                built.push(s);
+            },
+        }
+    }
+    Ok(built.pop().unwrap())
+}
+
+impl TurnerExpr {
+    pub fn from_lambda(expr: LambdaExpr, algorithm: Abstraction) -> Result<TurnerExpr, String> {
+        match algorithm {
+            Abstraction::Kiselyov | Abstraction::KiselyovBulk =>
+                TurnerExpr::from_int(kiselyov(&DbExpr::from(expr), algorithm)?.1),
+            Abstraction::KiselyovEta => {
+                let s = kiselyov_eta(&DbExpr::from(expr))?;
+                // closed terms use no variables
+                assert!(s.uses.is_empty());
+                TurnerExpr::from_int(s.term)
+            },
+            _ => TurnerExpr::from_int(IntExpr::from(expr).compile(algorithm)),
+        }
+    }
+
+    fn from_int(expr: IntExpr) -> Result<TurnerExpr, String> {
+        from_int(expr, |e| Some(match *e {
+            IntExpr::S => TurnerExpr::S,
+            IntExpr::K => TurnerExpr::K,
+            IntExpr::I => TurnerExpr::I,
+            IntExpr::B => TurnerExpr::B,
+            IntExpr::C => TurnerExpr::C,
+            IntExpr::SPrime => TurnerExpr::SPrime,
+            IntExpr::BStar => TurnerExpr::BStar,
+            IntExpr::CPrime => TurnerExpr::CPrime,
+            IntExpr::BulkB(n) => TurnerExpr::BulkB(n),
+            IntExpr::BulkC(n) => TurnerExpr::BulkC(n),
+            IntExpr::BulkS(n) => TurnerExpr::BulkS(n),
+            _ => unreachable!(),
+        }))
+    }
+}
+
+impl From<SKIExpr> for TurnerExpr {
+    fn from(expr: SKIExpr) -> TurnerExpr {
+        expr.fold(|e| match e {
+            SKIExpr::Apply(..) => unreachable!(),
+            SKIExpr::S => TurnerExpr::S,
+            SKIExpr::K => TurnerExpr::K,
+            SKIExpr::I => TurnerExpr::I,
+        }, TurnerExpr::apply)
+    }
+}
+
+/// Expands the extended combinators into S, K and I.
+impl From<TurnerExpr> for SKIExpr {
+    fn from(expr: TurnerExpr) -> SKIExpr {
+        use expr::ski::SKIExpr::{S, K, I};
+        let ap = SKIExpr::apply;
+        // B = S (K S) K and C = S (S (K B) S) (K K)
+        let b = || ap(ap(S, ap(K, S)), K);
+        let c = || ap(ap(S, ap(ap(S, ap(K, b())), S)), ap(K, K));
+        expr.fold(|e| match *e {
+            TurnerExpr::Apply(..) => unreachable!(),
+            TurnerExpr::S => S,
+            TurnerExpr::K => K,
+            TurnerExpr::I => I,
+            TurnerExpr::B => b(),
+            TurnerExpr::C => c(),
+            // S' = B (B S) B
+            TurnerExpr::SPrime => ap(ap(b(), ap(b(), S)), b()),
+            // B* = B (C B B) (B B B)
+            TurnerExpr::BStar => ap(ap(b(), ap(ap(c(), b()), b())), ap(ap(b(), b()), b())),
+            // C' = B (B C) B
+            TurnerExpr::CPrime => ap(ap(b(), ap(b(), c())), b()),
+            // B(n+1) = B B Bn, C(n+1) = B C (B Cn), S(n+1) = B S (B Sn)
+            TurnerExpr::BulkB(n) => (1..n).fold(b(), |e, _| ap(ap(b(), b()), e)),
+            TurnerExpr::BulkC(n) => (1..n).fold(c(), |e, _| ap(ap(b(), c()), ap(b(), e))),
+            TurnerExpr::BulkS(n) => (1..n).fold(S, |e, _| ap(ap(b(), S), ap(b(), e))),
+        }, ap)
+    }
 }
 
 impl fmt::Display for IntExpr {
@@ -156,9 +795,7 @@ impl fmt::Display for SKIExpr {
 impl From<IotaExpr> for SKIExpr {
     fn from(expr: IotaExpr) -> SKIExpr {
         // iota := S (S I (K S)) (K K)
-        match expr {
-            IotaExpr::Apply(e1, e2) => SKIExpr::Apply(Box::new(SKIExpr::from(*e1)), Box::new(SKIExpr::from(*e2))),
-            IotaExpr::Iota => SKIExpr::Apply(
+        expr.fold(|_| SKIExpr::Apply(
                 Box::new(SKIExpr::Apply(
                         Box::new(SKIExpr::S),
                         Box::new(SKIExpr::Apply(
@@ -166,15 +803,20 @@ impl From<IotaExpr> for SKIExpr {
                                 Box::new(SKIExpr::Apply(Box::new(SKIExpr::K), Box::new(SKIExpr::S))))))),
                 Box::new(SKIExpr::Apply(
                         Box::new(SKIExpr::K),
-                        Box::new(SKIExpr::K)))),
-        }
+                        Box::new(SKIExpr::K)))), SKIExpr::apply)
+    }
+}
+
+impl fmt::Display for TurnerExpr {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        write!(fmt, "{}", IntExpr::from(self.clone()).display_lambda())
     }
 }
 
 impl From<SKIExpr> for IotaExpr {
     fn from(expr: SKIExpr) -> IotaExpr {
-        match expr {
-            SKIExpr::Apply(e1, e2) => IotaExpr::Apply(Box::new(IotaExpr::from(*e1)), Box::new(IotaExpr::from(*e2))),
+        expr.fold(|e| match e {
+            SKIExpr::Apply(..) => unreachable!(),
             SKIExpr::S => IotaExpr::Apply(Box::new(IotaExpr::Iota),
                     Box::new(IotaExpr::Apply(Box::new(IotaExpr::Iota),
                         Box::new(IotaExpr::Apply(Box::new(IotaExpr::Iota),
@@ -183,16 +825,13 @@ impl From<SKIExpr> for IotaExpr {
                     Box::new(IotaExpr::Apply(Box::new(IotaExpr::Iota),
                         Box::new(IotaExpr::Apply(Box::new(IotaExpr::Iota), Box::new(IotaExpr::Iota)))))),
             SKIExpr::I => IotaExpr::Apply(Box::new(IotaExpr::Iota), Box::new(IotaExpr::Iota)),
-        }
+        }, IotaExpr::apply)
     }
 }
 
 impl IotaExpr {
     fn display_lambda(&self) -> LambdaExpr {
-        match self {
-            &IotaExpr::Apply(ref e1, ref e2) => LambdaExpr::Apply(Box::new(e1.display_lambda()), Box::new(e2.display_lambda())),
-            &IotaExpr::Iota => LambdaExpr::Variable("ι".to_string()),
-        }
+        self.fold(|_| LambdaExpr::Variable("ι".to_string()), LambdaExpr::apply)
     }
 }
 
@@ -202,10 +841,213 @@ impl fmt::Display for IotaExpr {
     }
 }
 
+impl DbExpr {
+    fn from_lambda(expr: LambdaExpr) -> DbExpr {
+        enum Task {
+            Visit(LambdaExpr),
+            Apply,
+            Lambda,
+        }
+        // binders enclosing the term being visited, innermost last
+        let mut names = Vec::new();
+        let mut tasks = vec![Task::Visit(expr)];
+        let mut built = Vec::new();
+        while let Some(task) = tasks.pop() {
+            match task {
+                Task::Visit(e) => match e.into_node() {
+                    Node::Variable(v) => built.push(match names.iter().rev().position(|n| *n == v) {
+                        Some(i) => DbExpr::Bound(i),
+                        None => DbExpr::Free(v),
+                    }),
+                    Node::Apply(e1, e2) => {
+                        tasks.push(Task::Apply);
+                        tasks.push(Task::Visit(e2));
+                        tasks.push(Task::Visit(e1));
+                    },
+                    Node::Lambda(v, e) => {
+                        names.push(v);
+                        tasks.push(Task::Lambda);
+                        tasks.push(Task::Visit(e));
+                    },
+                },
+                Task::Apply => {
+                    let e2 = built.pop().unwrap();
+                    let e1 = built.pop().unwrap();
+                    built.push(DbExpr::apply(e1, e2));
+                },
+                Task::Lambda => {
+                    let e = built.pop().unwrap();
+                    built.push(DbExpr::Lambda(names.pop().unwrap(), Box::new(e)));
+                },
+            }
+        }
+        built.pop().unwrap()
+    }
+
+    fn lookup(names: &[String], index: usize) -> Option<&String> {
+        names.len().checked_sub(index + 1).map(|i| &names[i])
+    }
+
+    /// Whether a variable called `name` would be needed to refer to
+    /// something other than the binder `depth` levels above this term.
+    fn mentions(&self, name: &str, depth: usize, names: &[String]) -> bool {
+        let mut stack = vec![(self, depth)];
+        while let Some((e, depth)) = stack.pop() {
+            match e {
+                DbExpr::Free(v) => if v == name { return true; },
+                DbExpr::Bound(i) => if *i >= depth && DbExpr::lookup(names, i - depth).is_some_and(|n| n == name) {
+                    return true;
+                },
+                DbExpr::Apply(e1, e2) => {
+                    stack.push((e2, depth));
+                    stack.push((e1, depth));
+                },
+                DbExpr::Lambda(_, e) => stack.push((e, depth + 1)),
+            }
+        }
+        false
+    }
+
+    fn to_lambda(&self) -> LambdaExpr {
+        enum Task<'a> {
+            Visit(&'a DbExpr),
+            Apply,
+            Lambda,
+        }
+        // names chosen for the binders enclosing the term being visited,
+        // innermost last
+        let mut names = Vec::new();
+        let mut tasks = vec![Task::Visit(self)];
+        let mut built = Vec::new();
+        while let Some(task) = tasks.pop() {
+            match task {
+                Task::Visit(DbExpr::Free(v)) => built.push(LambdaExpr::Variable(v.clone())),
+                Task::Visit(DbExpr::Bound(i)) => built.push(LambdaExpr::Variable(match DbExpr::lookup(&names, *i) {
+                    Some(n) => n.clone(),
+                    None => format!("#{}", i - names.len()),
+                })),
+                Task::Visit(DbExpr::Apply(e1, e2)) => {
+                    tasks.push(Task::Apply);
+                    tasks.push(Task::Visit(e2));
+                    tasks.push(Task::Visit(e1));
+                },
+                Task::Visit(DbExpr::Lambda(hint, e)) => {
+                    let hint = if hint.is_empty() { "x" } else { hint };
+                    let mut name = hint.to_string();
+                    let mut suffix = 0;
+                    while e.mentions(&name, 1, &names) {
+                        suffix += 1;
+                        name = format!("{}{}", hint, suffix);
+                    }
+                    names.push(name);
+                    tasks.push(Task::Lambda);
+                    tasks.push(Task::Visit(e));
+                },
+                Task::Apply => {
+                    let e2 = built.pop().unwrap();
+                    let e1 = built.pop().unwrap();
+                    built.push(LambdaExpr::apply(e1, e2));
+                },
+                Task::Lambda => {
+                    let e = built.pop().unwrap();
+                    built.push(LambdaExpr::Lambda(names.pop().unwrap(), Box::new(e)));
+                },
+            }
+        }
+        built.pop().unwrap()
+    }
+
+    fn display_lambda(&self) -> LambdaExpr {
+        enum Task<'a> {
+            Visit(&'a DbExpr),
+            Apply,
+            Lambda,
+        }
+        let mut tasks = vec![Task::Visit(self)];
+        let mut built = Vec::new();
+        while let Some(task) = tasks.pop() {
+            match task {
+                Task::Visit(DbExpr::Free(v)) => built.push(LambdaExpr::Variable(v.clone())),
+                Task::Visit(DbExpr::Bound(i)) => built.push(LambdaExpr::Variable(i.to_string())),
+                Task::Visit(DbExpr::Apply(e1, e2)) => {
+                    tasks.push(Task::Apply);
+                    tasks.push(Task::Visit(e2));
+                    tasks.push(Task::Visit(e1));
+                },
+                Task::Visit(DbExpr::Lambda(_, e)) => {
+                    tasks.push(Task::Lambda);
+                    tasks.push(Task::Visit(e));
+                },
+                Task::Apply => {
+                    let e2 = built.pop().unwrap();
+                    let e1 = built.pop().unwrap();
+                    built.push(LambdaExpr::apply(e1, e2));
+                },
+                Task::Lambda => {
+                    let e = built.pop().unwrap();
+                    built.push(LambdaExpr::Lambda(String::new(), Box::new(e)));
+                },
+            }
+        }
+        built.pop().unwrap()
+    }
+}
+
+/// Type erasure: drops annotations, type abstractions and type applications.
+impl From<FExpr> for LambdaExpr {
+    fn from(expr: FExpr) -> LambdaExpr {
+        match expr {
+            FExpr::Variable(v) => LambdaExpr::Variable(v),
+            FExpr::Apply(e1, e2) => LambdaExpr::Apply(Box::new(LambdaExpr::from(*e1)), Box::new(LambdaExpr::from(*e2))),
+            FExpr::Lambda(v, _, e) => LambdaExpr::Lambda(v, Box::new(LambdaExpr::from(*e))),
+            FExpr::TypeLambda(_, e) | FExpr::TypeApply(e, _) => LambdaExpr::from(*e),
+        }
+    }
+}
+
+impl From<LambdaExpr> for DbExpr {
+    fn from(expr: LambdaExpr) -> DbExpr {
+        DbExpr::from_lambda(expr)
+    }
+}
+
+/// Binder names are taken from the hints in `DbExpr::Lambda`, with a numeric
+/// suffix appended where the hint would capture another variable.
+impl From<DbExpr> for LambdaExpr {
+    fn from(expr: DbExpr) -> LambdaExpr {
+        expr.to_lambda()
+    }
+}
+
+impl fmt::Display for DbExpr {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        write!(fmt, "{}", self.display_lambda())
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use convert::*;
     use expr::lambda;
+    use encoding::{numeral, Encoding};
+
+    #[test]
+    fn deep_terms_to_iota() {
+        // deeper than the recursion the default test thread stack allows
+        let n = 10000;
+        let deep = numeral(n, Encoding::Church);
+        assert_eq!(2 * n as usize + 3, deep.size());
+        for alg in Abstraction::all() {
+            let turner = TurnerExpr::from_lambda(deep.clone(), *alg).unwrap();
+            assert!(turner.size() > n as usize, "{}", alg);
+            assert_eq!(turner, turner.clone());
+            assert!(IntExpr::from(turner.clone()).display_lambda().size() == turner.size());
+            let iota = IotaExpr::from(SKIExpr::from(turner));
+            assert_eq!(iota.size(), iota.to_string().len());
+            assert_eq!(iota, iota.clone());
+            assert_eq!(iota.size(), iota.display_lambda().size());
+        }
+    }
 
     #[test]
     fn id_to_ski() {
@@ -228,4 +1070,157 @@ mod tests {
         assert_eq!("ι (ι (ι ι))", format!("{}", IotaExpr::from(SKIExpr::K)));
         assert_eq!("ι (ι (ι (ι ι)))", format!("{}", IotaExpr::from(SKIExpr::S)));
     }
+
+    fn turner(s: &str, algorithm: Abstraction) -> TurnerExpr {
+        TurnerExpr::from_lambda(lambda::parse(s).unwrap(), algorithm).unwrap()
+    }
+
+    #[test]
+    fn basic_matches_ski() {
+        for s in &["\\x \\y y x", "\\m \\n \\f \\x m f (n f x)", "\\f (\\x f (x x)) (\\x f (x x))"] {
+            let ski = SKIExpr::from_lambda(lambda::parse(s).unwrap()).unwrap();
+            assert_eq!(TurnerExpr::from(ski), turner(s, Abstraction::Basic));
+        }
+    }
+
+    #[test]
+    fn curry_to_turner() {
+        assert_eq!("C I", format!("{}", turner("\\x \\y y x", Abstraction::Curry)));
+        assert_eq!("B", format!("{}", turner("\\f \\g \\x f (g x)", Abstraction::Curry)));
+        assert_eq!("C", format!("{}", turner("\\f \\g \\x f x g", Abstraction::Curry)));
+    }
+
+    #[test]
+    fn turner_combinators() {
+        assert_eq!("S'", format!("{}", turner("\\c \\f \\g \\x c (f x) (g x)", Abstraction::Turner)));
+        assert_eq!("B*", format!("{}", turner("\\c \\f \\g \\x c (f (g x))", Abstraction::Turner)));
+        assert_eq!("C'", format!("{}", turner("\\c \\f \\g \\x c (f x) g", Abstraction::Turner)));
+    }
+
+    #[test]
+    fn turner_to_ski() {
+        // B S K I = S (K I)
+        let e = TurnerExpr::apply(TurnerExpr::apply(TurnerExpr::apply(TurnerExpr::B, TurnerExpr::S), TurnerExpr::K), TurnerExpr::I);
+        assert_eq!("S (K I)", format!("{}", SKIExpr::from(e).repeated_reduce(100).0));
+    }
+
+    #[test]
+    fn turner_expansions() {
+        let combinators = vec![
+            (TurnerExpr::B, "\\f \\g \\x f (g x)"),
+            (TurnerExpr::C, "\\f \\g \\x f x g"),
+            (TurnerExpr::SPrime, "\\c \\f \\g \\x c (f x) (g x)"),
+            (TurnerExpr::BStar, "\\c \\f \\g \\x c (f (g x))"),
+            (TurnerExpr::CPrime, "\\c \\f \\g \\x c (f x) g"),
+            (TurnerExpr::BulkC(2), "\\f \\g \\x \\y f x y g"),
+        ];
+        for (comb, definition) in combinators {
+            let (e, _) = SKIExpr::from(comb.clone()).decompile().repeated_beta(1000);
+            assert!(e.alpha_eq(&lambda::parse(definition).unwrap()), "{:?} expanded to {}", comb, e);
+        }
+    }
+
+    #[test]
+    fn turner_smaller() {
+        let s = "\\m \\n \\f \\x m f (n f x)";
+        let basic = turner(s, Abstraction::Basic).size();
+        let curry = turner(s, Abstraction::Curry).size();
+        let turner = turner(s, Abstraction::Turner).size();
+        assert!(curry < basic);
+        assert!(turner <= curry);
+    }
+
+    fn kiselyov_run(s: &str, args: Vec<SKIExpr>, algorithm: Abstraction) -> SKIExpr {
+        let mut e = SKIExpr::from(turner(s, algorithm));
+        for arg in args {
+            e = SKIExpr::apply(e, arg);
+        }
+        e.repeated_reduce(10000).0
+    }
+
+    #[test]
+    fn kiselyov_plain() {
+        assert_eq!("I", format!("{}", turner("\\x x", Abstraction::Kiselyov)));
+        assert_eq!("K", format!("{}", turner("\\x \\y x", Abstraction::KiselyovEta)));
+        assert_eq!("C I", format!("{}", turner("\\x \\y y x", Abstraction::KiselyovEta)));
+    }
+
+    #[test]
+    fn kiselyov_bulk() {
+        assert_eq!("B (S2 (C (B S K) I)) (C (B B2 K) I)", format!("{}", turner("\\x \\y \\z y z x", Abstraction::KiselyovBulk)));
+    }
+
+    #[test]
+    fn kiselyov_bulk_linear() {
+        // n uses of a variable under n binders, so indices add up to n²
+        let n = 40;
+        let params: Vec<String> = (0..n).map(|i| format!("x{}", i)).collect();
+        let body = (0..n).map(|_| "x0").collect::<Vec<_>>().join(" ");
+        let e = lambda::parse(&format!("\\{} {}", params.join(" \\"), body)).unwrap();
+        let size = e.size();
+        assert!(TurnerExpr::from_lambda(e.clone(), Abstraction::KiselyovBulk).unwrap().size() < 5 * size);
+        assert!(TurnerExpr::from_lambda(e, Abstraction::Kiselyov).unwrap().size() > n * n);
+    }
+
+    #[test]
+    fn kiselyov_semantics() {
+        use expr::ski::SKIExpr::*;
+        let cases = vec![
+            ("\\x \\y \\z x z (y z)", vec![K, S, I]),
+            ("\\a \\b \\c \\d d c b a", vec![S, K, I, K]),
+            ("\\a \\b \\c \\d c (a d) (b b)", vec![I, K, K, S]),
+            ("\\x \\y \\z y z x", vec![I, K, S]),
+            // not (not (not true)) = false
+            ("(\\n n (\\b \\x \\y b y x) (\\x \\y x)) (\\f \\x f (f (f x)))", vec![K, S]),
+        ];
+        for &(s, ref args) in &cases {
+            let expected = kiselyov_run(s, args.clone(), Abstraction::Basic);
+            assert!(expected == K || expected == S || expected == I, "{} gave {}", s, expected);
+            for alg in Abstraction::all() {
+                assert_eq!(expected, kiselyov_run(s, args.clone(), *alg), "{} with {}", s, alg);
+            }
+        }
+    }
+
+    #[test]
+    fn kiselyov_free_variable() {
+        assert_eq!(Err("free variable: y".to_string()), TurnerExpr::from_lambda(lambda::parse("\\x y").unwrap(), Abstraction::Kiselyov));
+    }
+
+    #[test]
+    fn kiselyov_factorial_size() {
+        let e = lambda::parse(include_str!("../factorial.lambda")).unwrap();
+        let basic = TurnerExpr::from_lambda(e.clone(), Abstraction::Basic).unwrap().size();
+        let bulk = TurnerExpr::from_lambda(e, Abstraction::KiselyovBulk).unwrap().size();
+        assert!(bulk < basic, "{} vs {}", bulk, basic);
+    }
+
+    #[test]
+    fn lambda_to_debruijn() {
+        assert_eq!("λ λ 1", format!("{}", DbExpr::from(lambda::parse("\\x \\y x").unwrap())));
+        assert_eq!("λ λ f 0 1", format!("{}", DbExpr::from(lambda::parse("\\x \\y f y x").unwrap())));
+        assert_eq!("λ λ 0", format!("{}", DbExpr::from(lambda::parse("\\x \\x x").unwrap())));
+    }
+
+    #[test]
+    fn debruijn_alpha_equal() {
+        assert_eq!(DbExpr::from(lambda::parse("\\x \\y y x").unwrap()), DbExpr::from(lambda::parse("\\a \\b b a").unwrap()));
+        assert!(DbExpr::from(lambda::parse("\\x \\y x").unwrap()) != DbExpr::from(lambda::parse("\\x \\y y").unwrap()));
+    }
+
+    #[test]
+    fn debruijn_round_trip() {
+        for s in &["x", "\\x \\x x", "\\m \\n \\f \\x m f (n f x)", "\\y' \\y y' (\\y y) z"] {
+            let e = lambda::parse(s).unwrap();
+            assert_eq!(e.clone(), LambdaExpr::from(DbExpr::from(e)));
+        }
+    }
+
+    #[test]
+    fn debruijn_to_lambda_renames() {
+        let e = DbExpr::Lambda("x".to_string(), Box::new(DbExpr::Apply(
+            Box::new(DbExpr::Free("x".to_string())),
+            Box::new(DbExpr::Bound(0)))));
+        assert_eq!("λx1 x x1", format!("{}", LambdaExpr::from(e)));
+    }
 }
@@ -0,0 +1,282 @@
+use expr::lambda::{LambdaExpr, Node};
+use expr::ski::SKIExpr;
+use expr::iota::IotaExpr;
+use expr::turner::TurnerExpr;
+use expr::debruijn::DbExpr;
+use expr::literal::{lam, lambdas, var};
+use alpha::canonical_name;
+
+/// Number of beta-reductions tried when looking for a normal form
+/// smaller than the simplified term.
+const NORMALIZE_LIMIT: u32 = 1000;
+
+struct Fresh {
+    next: usize,
+}
+
+impl Fresh {
+    fn name(&mut self) -> String {
+        self.next += 1;
+        format!("#{}", self.next)
+    }
+
+    fn names(&mut self, n: usize) -> Vec<String> {
+        (0..n).map(|_| self.name()).collect()
+    }
+}
+
+/// Definition of a combinator, with binders that are fresh in the whole term.
+fn combinator(expr: &TurnerExpr, fresh: &mut Fresh) -> LambdaExpr {
+    let ap = LambdaExpr::apply;
+    let (f, g, x) = (fresh.name(), fresh.name(), fresh.name());
+    match *expr {
+        TurnerExpr::Apply(_, _) => unreachable!(),
+        TurnerExpr::S => lambdas(vec![f.clone(), g.clone(), x.clone()],
+            ap(ap(var(&f), var(&x)), ap(var(&g), var(&x)))),
+        TurnerExpr::K => lam(&f, lam(&g, var(&f))),
+        TurnerExpr::I => lam(&x, var(&x)),
+        TurnerExpr::B => lambdas(vec![f.clone(), g.clone(), x.clone()],
+            ap(var(&f), ap(var(&g), var(&x)))),
+        TurnerExpr::C => lambdas(vec![f.clone(), g.clone(), x.clone()],
+            ap(ap(var(&f), var(&x)), var(&g))),
+        TurnerExpr::SPrime | TurnerExpr::BStar | TurnerExpr::CPrime => {
+            let c = fresh.name();
+            let body = match *expr {
+                TurnerExpr::SPrime => ap(ap(var(&c), ap(var(&f), var(&x))), ap(var(&g), var(&x))),
+                TurnerExpr::BStar => ap(var(&c), ap(var(&f), ap(var(&g), var(&x)))),
+                _ => ap(ap(var(&c), ap(var(&f), var(&x))), var(&g)),
+            };
+            lambdas(vec![c, f, g, x], body)
+        },
+        TurnerExpr::BulkB(n) | TurnerExpr::BulkC(n) | TurnerExpr::BulkS(n) => {
+            let xs = fresh.names(n);
+            // `head` applied to all of `xs`
+            let spine = |head: &str| xs.iter().fold(var(head), |e, x| ap(e, var(x)));
+            let body = match *expr {
+                TurnerExpr::BulkB(_) => ap(var(&f), spine(&g)),
+                TurnerExpr::BulkC(_) => ap(spine(&f), var(&g)),
+                _ => ap(spine(&f), spine(&g)),
+            };
+            lambdas(vec![f, g], lambdas(xs, body))
+        },
+    }
+}
+
+fn expand(expr: &TurnerExpr, fresh: &mut Fresh) -> LambdaExpr {
+    enum Task<'a> {
+        Visit(&'a TurnerExpr),
+        Apply,
+    }
+    let mut tasks = vec![Task::Visit(expr)];
+    let mut built = Vec::new();
+    while let Some(task) = tasks.pop() {
+        match task {
+            Task::Visit(TurnerExpr::Apply(e1, e2)) => {
+                tasks.push(Task::Apply);
+                tasks.push(Task::Visit(e2));
+                tasks.push(Task::Visit(e1));
+            },
+            Task::Visit(e) => built.push(combinator(e, fresh)),
+            Task::Apply => {
+                let e2 = built.pop().unwrap();
+                let e1 = built.pop().unwrap();
+                built.push(LambdaExpr::apply(e1, e2));
+            },
+        }
+    }
+    built.pop().unwrap()
+}
+
+impl LambdaExpr {
+    fn is_variable(&self) -> bool {
+        matches!(*self, LambdaExpr::Variable(_))
+    }
+}
+
+fn occurrences(expr: &LambdaExpr, var: &str) -> usize {
+    let mut count = 0;
+    let mut stack = vec![expr];
+    while let Some(e) = stack.pop() {
+        match e {
+            LambdaExpr::Variable(v) => if v == var { count += 1; },
+            LambdaExpr::Apply(e1, e2) => {
+                stack.push(e2);
+                stack.push(e1);
+            },
+            LambdaExpr::Lambda(v, e) => if v != var { stack.push(e); },
+        }
+    }
+    count
+}
+
+/// Whether the term is a redex whose contraction does not duplicate work:
+/// the argument is a variable, or the parameter is used at most once.
+fn is_contractible(expr: &LambdaExpr) -> bool {
+    match expr {
+        LambdaExpr::Apply(e1, e2) => match **e1 {
+            LambdaExpr::Lambda(ref v, ref body) => e2.is_variable() || occurrences(body, v) <= 1,
+            _ => false,
+        },
+        _ => false,
+    }
+}
+
+/// Performs the leftmost beta-reduction that does not duplicate work.
+/// Every such step makes the term smaller, so repeating it terminates.
+fn simplify(mut expr: LambdaExpr) -> (LambdaExpr, bool) {
+    let path = match expr.find_first(is_contractible) {
+        Some(path) => path,
+        None => return (expr, false),
+    };
+    let redex = expr.subterm_mut(&path);
+    if let Node::Apply(e1, e2) = redex.take().into_node() {
+        if let Node::Lambda(v, body) = e1.into_node() {
+            *redex = body.replace(&v, &e2);
+            return (expr, true);
+        }
+    }
+    unreachable!()
+}
+
+/// Normal form of the term, unless it takes more than `NORMALIZE_LIMIT`
+/// steps or the term grows too much on the way there.
+fn normalize(expr: LambdaExpr) -> Option<LambdaExpr> {
+    let max_size = 2 * expr.size() + 64;
+    let mut expr = expr;
+    for _ in 0..NORMALIZE_LIMIT {
+        let (next, res) = expr.beta();
+        if !res {
+            return Some(next);
+        }
+        if next.size() > max_size {
+            return None;
+        }
+        expr = next;
+    }
+    None
+}
+
+/// Names binders a, b, c, ... in the order they appear.
+fn rename(expr: DbExpr) -> DbExpr {
+    enum Task {
+        Visit(DbExpr),
+        Apply,
+        Lambda(String),
+    }
+    let mut counter = 0;
+    let mut tasks = vec![Task::Visit(expr)];
+    let mut built = Vec::new();
+    while let Some(task) = tasks.pop() {
+        match task {
+            Task::Visit(mut e) => match e {
+                DbExpr::Apply(ref mut e1, ref mut e2) => {
+                    tasks.push(Task::Apply);
+                    tasks.push(Task::Visit(e2.take()));
+                    tasks.push(Task::Visit(e1.take()));
+                },
+                DbExpr::Lambda(_, ref mut body) => {
+                    tasks.push(Task::Lambda(canonical_name(counter)));
+                    counter += 1;
+                    tasks.push(Task::Visit(body.take()));
+                },
+                _ => built.push(e),
+            },
+            Task::Apply => {
+                let e2 = built.pop().unwrap();
+                let e1 = built.pop().unwrap();
+                built.push(DbExpr::apply(e1, e2));
+            },
+            Task::Lambda(name) => {
+                let e = built.pop().unwrap();
+                built.push(DbExpr::Lambda(name, Box::new(e)));
+            },
+        }
+    }
+    built.pop().unwrap()
+}
+
+impl TurnerExpr {
+    /// Reconstructs a readable lambda term: combinators are expanded, then
+    /// beta-reduced wherever that does not duplicate an argument, or to
+    /// normal form if one is found quickly and is smaller, and finally
+    /// eta-reduced. Every binder in the result has a distinct name.
+    pub fn decompile(&self) -> LambdaExpr {
+        let mut expr = expand(self, &mut Fresh { next: 0 });
+        loop {
+            let (next, res) = simplify(expr);
+            expr = next;
+            if !res { break; }
+        }
+        if let Some(normal) = normalize(expr.clone()) {
+            if normal.size() <= expr.size() {
+                expr = normal;
+            }
+        }
+        loop {
+            let (next, res) = expr.eta();
+            expr = next;
+            if !res { break; }
+        }
+        LambdaExpr::from(rename(DbExpr::from(expr)))
+    }
+}
+
+impl SKIExpr {
+    /// Reconstructs a readable lambda term; see `TurnerExpr::decompile`.
+    pub fn decompile(&self) -> LambdaExpr {
+        TurnerExpr::from(self.clone()).decompile()
+    }
+}
+
+impl IotaExpr {
+    /// Reconstructs a readable lambda term, with `ι` read as `λf f S K`;
+    /// see `TurnerExpr::decompile`.
+    pub fn decompile(&self) -> LambdaExpr {
+        SKIExpr::from(self.clone()).decompile()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use expr::lambda::parse;
+    use expr::ski::SKIExpr;
+    use expr::iota;
+    use expr::turner::TurnerExpr;
+    use convert::Abstraction;
+
+    #[test]
+    fn decompile_combinators() {
+        assert_eq!(parse("\\a \\b \\c a c (b c)").unwrap(), SKIExpr::S.decompile());
+        assert_eq!(parse("\\a \\b a").unwrap(), SKIExpr::K.decompile());
+        assert_eq!(parse("\\a a").unwrap(), SKIExpr::I.decompile());
+    }
+
+    #[test]
+    fn decompile_flip() {
+        let ski = SKIExpr::from_lambda(parse("\\x \\y y x").unwrap()).unwrap();
+        assert_eq!("S (K (S I)) K", format!("{}", ski));
+        assert_eq!(parse("\\a \\b b a").unwrap(), ski.decompile());
+    }
+
+    #[test]
+    fn decompile_iota() {
+        assert_eq!(parse("\\a a").unwrap(), iota::parse("*ii").unwrap().decompile());
+    }
+
+    #[test]
+    fn decompile_round_trip() {
+        for s in &["\\m \\n \\f \\x m f (n f x)", "\\n \\f \\x f (n f x)", "\\a \\b \\c c (a b) b"] {
+            let e = parse(s).unwrap();
+            for alg in Abstraction::all() {
+                let compiled = TurnerExpr::from_lambda(e.clone(), *alg).unwrap();
+                assert!(e.alpha_eq(&compiled.decompile()), "{} with {}: {}", s, alg, compiled.decompile());
+            }
+        }
+    }
+
+    #[test]
+    fn decompile_y_terminates() {
+        let y = SKIExpr::from_lambda(parse("\\f (\\x f (x x)) (\\x f (x x))").unwrap()).unwrap();
+        assert_eq!(parse("\\a a ((\\b b b) (\\c a (c c)))").unwrap(), y.decompile());
+    }
+}
@@ -0,0 +1,359 @@
+use expr::lambda::LambdaExpr;
+use expr::literal::{fresh, lam, var};
+pub use expr::literal::{list, numeral, Encoding};
+
+use std::collections::HashSet;
+
+/// `λa λb body`, as the names and body.
+fn binary(expr: &LambdaExpr) -> Option<(&str, &str, &LambdaExpr)> {
+    match expr {
+        LambdaExpr::Lambda(a, e) => match **e {
+            LambdaExpr::Lambda(ref b, ref body) => Some((a, b, body)),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// Whether `v` in `λa λb ...` refers to `a`, which `b` may shadow.
+fn is_outer(v: &str, a: &str, b: &str) -> bool {
+    v == a && v != b
+}
+
+/// `f x y`, as the three parts.
+fn apply2(expr: &LambdaExpr) -> Option<(&LambdaExpr, &LambdaExpr, &LambdaExpr)> {
+    match expr {
+        LambdaExpr::Apply(e1, y) => match **e1 {
+            LambdaExpr::Apply(ref f, ref x) => Some((f, x, y)),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// `expr`, if none of `names` is free in it.
+fn avoiding<'a>(expr: &'a LambdaExpr, names: &[&str]) -> Option<&'a LambdaExpr> {
+    if names.iter().any(|name| expr.contains(name)) { None } else { Some(expr) }
+}
+
+fn is_var(expr: &LambdaExpr, name: &str) -> bool {
+    matches!(expr, LambdaExpr::Variable(v) if v == name)
+}
+
+/// `λa λb a` for true and `λa λb b` for false, in either encoding.
+pub fn boolean(b: bool) -> LambdaExpr {
+    lam("a", lam("b", var(if b { "a" } else { "b" })))
+}
+
+/// `λf f a b`, in either encoding.
+pub fn pair(a: LambdaExpr, b: LambdaExpr) -> LambdaExpr {
+    let f = fresh("f", &[&a, &b]);
+    lam(&f, LambdaExpr::apply(LambdaExpr::apply(var(&f), a), b))
+}
+
+/// The components of a pair built as by `pair`, up to alpha-equivalence.
+pub fn pair_items(expr: &LambdaExpr) -> Option<(&LambdaExpr, &LambdaExpr)> {
+    match expr {
+        LambdaExpr::Lambda(f, body) => {
+            let (g, a, b) = apply2(body)?;
+            if is_var(g, f) { Some((avoiding(a, &[f])?, avoiding(b, &[f])?)) } else { None }
+        },
+        _ => None,
+    }
+}
+
+/// The items of a list built as by `list`, up to alpha-equivalence.
+pub fn list_items(expr: &LambdaExpr, encoding: Encoding) -> Option<Vec<&LambdaExpr>> {
+    let mut items = Vec::new();
+    let (c, n, mut body) = binary(expr)?;
+    match encoding {
+        Encoding::Church => {
+            // λc λn c a (c b n)
+            loop {
+                if is_var(body, n) {
+                    return Some(items);
+                }
+                let (g, head, tail) = apply2(body)?;
+                if !is_var(g, c) || !is_outer(c, c, n) {
+                    return None;
+                }
+                items.push(avoiding(head, &[c, n])?);
+                body = tail;
+            }
+        },
+        Encoding::Scott => {
+            // λc λn n, or λc λn c a l for l the tail
+            if is_var(body, n) {
+                return Some(items);
+            }
+            let (g, head, tail) = apply2(body)?;
+            if !is_var(g, c) || !is_outer(c, c, n) {
+                return None;
+            }
+            items.push(avoiding(head, &[c, n])?);
+            items.extend(list_items(avoiding(tail, &[c, n])?, encoding)?);
+            Some(items)
+        },
+    }
+}
+
+fn names<'a>(expr: &'a LambdaExpr, out: &mut HashSet<&'a str>) {
+    match expr {
+        LambdaExpr::Variable(v) => { out.insert(v); },
+        LambdaExpr::Apply(e1, e2) => { names(e1, out); names(e2, out); },
+        LambdaExpr::Lambda(v, e) => { out.insert(v); names(e, out); },
+    }
+}
+
+fn quote_with(expr: &LambdaExpr, v: &str, a: &str, l: &str) -> LambdaExpr {
+    let body = match expr {
+        LambdaExpr::Variable(x) => LambdaExpr::apply(var(v), var(x)),
+        LambdaExpr::Apply(e1, e2) => LambdaExpr::apply(LambdaExpr::apply(var(a), quote_with(e1, v, a, l)), quote_with(e2, v, a, l)),
+        LambdaExpr::Lambda(x, e) => LambdaExpr::apply(var(l), lam(x, quote_with(e, v, a, l))),
+    };
+    lam(v, lam(a, lam(l, body)))
+}
+
+/// Mogensen-Scott encoding of a term, using the term's own binders:
+/// `⌜x⌝ = λv λa λl v x`, `⌜M N⌝ = λv λa λl a ⌜M⌝ ⌜N⌝` and `⌜λx M⌝ = λv λa λl l (λx ⌜M⌝)`.
+/// The names `v`, `a` and `l` are primed as needed to avoid those in the term.
+pub fn quote(expr: &LambdaExpr) -> LambdaExpr {
+    let mut used = HashSet::new();
+    names(expr, &mut used);
+    let fresh = |base: &str| {
+        let mut name = base.to_string();
+        while used.contains(&name[..]) {
+            name.push('\'');
+        }
+        name
+    };
+    quote_with(expr, &fresh("v"), &fresh("a"), &fresh("l"))
+}
+
+/// The term encoded by `quote`, up to alpha-equivalence.
+pub fn unquote(expr: &LambdaExpr) -> Option<LambdaExpr> {
+    let (v, rest) = match expr {
+        LambdaExpr::Lambda(v, rest) => (v, rest),
+        _ => return None,
+    };
+    let (a, l, body) = binary(rest)?;
+    if v == a || v == l || a == l {
+        return None;
+    }
+    // quoted subterms must not refer to these binders
+    let binders = [&v[..], a, l];
+    match body {
+        LambdaExpr::Apply(f, x) => match (&**f, &**x) {
+            (LambdaExpr::Variable(f), LambdaExpr::Variable(x)) if f == v && x != v && x != a && x != l =>
+                Some(var(x)),
+            (LambdaExpr::Variable(f), LambdaExpr::Lambda(x, e)) if f == l && x != v && x != a && x != l =>
+                Some(lam(x, unquote(avoiding(e, &binders)?)?)),
+            (LambdaExpr::Apply(g, e1), e2) if is_var(g, a) =>
+                Some(LambdaExpr::apply(unquote(avoiding(e1, &binders)?)?, unquote(avoiding(e2, &binders)?)?)),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// Rust values with a representation as lambda terms.
+pub trait Encode {
+    fn encode(&self, encoding: Encoding) -> LambdaExpr;
+}
+
+/// Rust values that can be read back from their representation, up to alpha-equivalence.
+pub trait Decode: Sized {
+    fn decode(expr: &LambdaExpr, encoding: Encoding) -> Option<Self>;
+}
+
+impl Encode for u64 {
+    fn encode(&self, encoding: Encoding) -> LambdaExpr {
+        numeral(*self, encoding)
+    }
+}
+
+impl Decode for u64 {
+    fn decode(expr: &LambdaExpr, encoding: Encoding) -> Option<u64> {
+        let (f, x, mut body) = binary(expr)?;
+        match encoding {
+            Encoding::Church => {
+                // λf λx f (f (... x))
+                let mut n = 0;
+                loop {
+                    match body {
+                        LambdaExpr::Variable(v) if v == x => return Some(n),
+                        LambdaExpr::Apply(e1, e2) if is_var(e1, f) && is_outer(f, f, x) => {
+                            n += 1;
+                            body = e2;
+                        },
+                        _ => return None,
+                    }
+                }
+            },
+            Encoding::Scott => {
+                // λs λz z, or λs λz s m for m the predecessor
+                match body {
+                    LambdaExpr::Variable(v) if v == x => Some(0),
+                    LambdaExpr::Apply(e1, e2) if is_var(e1, f) && is_outer(f, f, x) && !e2.contains(f) && !e2.contains(x) =>
+                        u64::decode(e2, encoding).map(|n| n + 1),
+                    _ => None,
+                }
+            },
+        }
+    }
+}
+
+impl Encode for bool {
+    fn encode(&self, _: Encoding) -> LambdaExpr {
+        boolean(*self)
+    }
+}
+
+impl Decode for bool {
+    fn decode(expr: &LambdaExpr, _: Encoding) -> Option<bool> {
+        match binary(expr)? {
+            (_, b, LambdaExpr::Variable(v)) if v == b => Some(false),
+            (a, b, LambdaExpr::Variable(v)) if is_outer(v, a, b) => Some(true),
+            _ => None,
+        }
+    }
+}
+
+/// Characters are encoded as the numeral of their code point.
+impl Encode for char {
+    fn encode(&self, encoding: Encoding) -> LambdaExpr {
+        numeral(*self as u64, encoding)
+    }
+}
+
+impl Decode for char {
+    fn decode(expr: &LambdaExpr, encoding: Encoding) -> Option<char> {
+        let n = u64::decode(expr, encoding)?;
+        if n > u32::MAX as u64 { None } else { ::std::char::from_u32(n as u32) }
+    }
+}
+
+/// Strings are encoded as lists of characters.
+impl Encode for String {
+    fn encode(&self, encoding: Encoding) -> LambdaExpr {
+        list(self.chars().map(|c| c.encode(encoding)).collect(), encoding)
+    }
+}
+
+impl Decode for String {
+    fn decode(expr: &LambdaExpr, encoding: Encoding) -> Option<String> {
+        list_items(expr, encoding)?.into_iter().map(|e| char::decode(e, encoding)).collect()
+    }
+}
+
+impl<T: Encode> Encode for Vec<T> {
+    fn encode(&self, encoding: Encoding) -> LambdaExpr {
+        list(self.iter().map(|x| x.encode(encoding)).collect(), encoding)
+    }
+}
+
+impl<T: Decode> Decode for Vec<T> {
+    fn decode(expr: &LambdaExpr, encoding: Encoding) -> Option<Vec<T>> {
+        list_items(expr, encoding)?.into_iter().map(|e| T::decode(e, encoding)).collect()
+    }
+}
+
+impl<A: Encode, B: Encode> Encode for (A, B) {
+    fn encode(&self, encoding: Encoding) -> LambdaExpr {
+        pair(self.0.encode(encoding), self.1.encode(encoding))
+    }
+}
+
+impl<A: Decode, B: Decode> Decode for (A, B) {
+    fn decode(expr: &LambdaExpr, encoding: Encoding) -> Option<(A, B)> {
+        let (a, b) = pair_items(expr)?;
+        Some((A::decode(a, encoding)?, B::decode(b, encoding)?))
+    }
+}
+
+/// Terms are encoded with `quote`, whatever the encoding.
+impl Encode for LambdaExpr {
+    fn encode(&self, _: Encoding) -> LambdaExpr {
+        quote(self)
+    }
+}
+
+impl Decode for LambdaExpr {
+    fn decode(expr: &LambdaExpr, _: Encoding) -> Option<LambdaExpr> {
+        unquote(expr)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use encoding::*;
+    use expr::lambda::parse;
+    use prelude;
+
+    #[test]
+    fn round_trip() {
+        for &enc in Encoding::all() {
+            assert_eq!(Some(5), u64::decode(&5u64.encode(enc), enc));
+            assert_eq!(Some(false), bool::decode(&false.encode(enc), enc));
+            let v = vec![(1u64, true), (0, false)];
+            assert_eq!(Some(v.clone()), Vec::decode(&v.encode(enc), enc));
+            let s = "λ x".to_string();
+            assert_eq!(Some(s.clone()), String::decode(&s.encode(enc), enc));
+            assert_eq!(None, u64::decode(&true.encode(enc), enc));
+        }
+    }
+
+    #[test]
+    fn decode_reduced() {
+        let (e, _) = prelude::parse("mul 2 (add 1 2)").unwrap().repeated_beta(10000);
+        assert_eq!(Some(6), u64::decode(&e, Encoding::Church));
+        let (e, _) = prelude::parse("map succ [0, 1]").unwrap().repeated_beta(10000);
+        assert_eq!(Some(vec![1, 2]), Vec::<u64>::decode(&e, Encoding::Church));
+    }
+
+    #[test]
+    fn scott_predecessor() {
+        // constant-time predecessor on Scott numerals
+        let pred = parse("\\n n (\\m m) n").unwrap();
+        let e = LambdaExpr::apply(pred, numeral(3, Encoding::Scott));
+        let (e, steps) = e.repeated_beta(100);
+        assert_eq!((Some(2), 4), (u64::decode(&e, Encoding::Scott), steps));
+    }
+
+    #[test]
+    fn quote_names() {
+        assert_eq!(parse("\\v \\a \\l l (\\x \\v \\a \\l v x)").unwrap(), quote(&parse("\\x x").unwrap()));
+        assert_eq!(parse("\\v' \\a \\l a (\\v' \\a \\l v' v) (\\v' \\a \\l v' w)").unwrap(),
+            quote(&parse("v w").unwrap()));
+    }
+
+    #[test]
+    fn quote_round_trip() {
+        for s in &["x", "\\x \\y x y", "(\\v v) (\\a \\l a l)", "\\x \\x x"] {
+            let e = parse(s).unwrap();
+            assert_eq!(Some(e.clone()), unquote(&quote(&e)));
+        }
+        assert_eq!(None, unquote(&parse("\\v \\a \\l v v").unwrap()));
+        assert_eq!(None, unquote(&numeral(2, Encoding::Church)));
+    }
+
+    #[test]
+    fn self_interpreter() {
+        // Mogensen's self-interpreter maps a quoted term to its value
+        let eval = parse("(\\f (\\x f (x x)) (\\x f (x x))) (\\e \\m m (\\x x) (\\m \\n e m (e n)) (\\m \\v e (m v)))").unwrap();
+        for s in &["(\\x \\y x) a b", "(\\f \\x f (f x)) (\\f \\x f (f x))", "\\x (\\y y) x"] {
+            let e = parse(s).unwrap();
+            let (expected, _) = e.clone().repeated_beta(1000);
+            let (actual, _) = LambdaExpr::apply(eval.clone(), quote(&e)).repeated_beta(10000);
+            assert!(actual.alpha_eq(&expected), "{} evaluated to {}, expected {}", s, actual, expected);
+        }
+    }
+
+    #[test]
+    fn quote_reduced() {
+        // a quoted term built by reduction
+        let e = parse("(\\q \\v \\a \\l a q q) (\\v \\a \\l v y)").unwrap();
+        let (e, _) = e.repeated_beta(10);
+        assert_eq!(Some(parse("y y").unwrap()), unquote(&e));
+    }
+}
@@ -0,0 +1,36 @@
+use expr::debruijn::DbExpr;
+
+use std::rc::Rc;
+
+/// Values bound by the enclosing lambdas, innermost (`Bound(0)`) first.
+/// Shared between the closures that capture it, so extending it is cheap.
+pub type Env<T> = Option<Rc<Scope<T>>>;
+
+pub struct Scope<T> {
+    value: T,
+    next: Env<T>,
+}
+
+pub fn extend<T>(env: &Env<T>, value: T) -> Env<T> {
+    Some(Rc::new(Scope { value, next: env.clone() }))
+}
+
+pub fn lookup<T: Clone>(env: &Env<T>, index: usize) -> T {
+    let mut scope = env.as_ref().expect("unbound index");
+    for _ in 0..index {
+        scope = scope.next.as_ref().expect("unbound index");
+    }
+    scope.value.clone()
+}
+
+/// Variable standing for the parameter of a lambda during readback,
+/// numbered by nesting depth.
+#[derive(Clone, Copy)]
+pub struct Level(pub usize);
+
+impl Level {
+    /// The variable as an index under `depth` nested lambdas.
+    pub fn at(self, depth: usize) -> DbExpr {
+        DbExpr::Bound(depth - self.0 - 1)
+    }
+}
@@ -0,0 +1,100 @@
+/// Term built by applying combinators to each other.
+///
+/// The provided methods use an explicit stack rather than recursion, so that
+/// deep terms do not overflow the stack; the term types build their `Clone`,
+/// `PartialEq` and `Drop` implementations on them.
+pub trait Combinator: Sized {
+    /// Function and argument of an application, or `None` for a combinator.
+    fn parts(&self) -> Option<(&Self, &Self)>;
+
+    /// Moves the function and argument out of an application, leaving
+    /// placeholders, or returns `None` for a combinator.
+    fn take_parts(&mut self) -> Option<(Self, Self)>;
+
+    /// Application of `e1` to `e2`.
+    fn join(e1: Self, e2: Self) -> Self;
+
+    /// Copy of a combinator. Never called on an application.
+    fn copy_leaf(&self) -> Self;
+
+    /// Whether two combinators are the same. Never called on applications.
+    fn same_leaf(&self, other: &Self) -> bool;
+
+    /// Rebuilds the term bottom-up, mapping each combinator with `leaf` and
+    /// each application with `apply`, which receives the results for the
+    /// function and the argument.
+    fn fold<T>(&self, mut leaf: impl FnMut(&Self) -> T, mut apply: impl FnMut(T, T) -> T) -> T {
+        // post-order: children are built before their parent
+        enum Task<'a, E> {
+            Visit(&'a E),
+            Apply,
+        }
+        let mut tasks = vec![Task::Visit(self)];
+        let mut built = Vec::new();
+        while let Some(task) = tasks.pop() {
+            match task {
+                Task::Visit(e) => match e.parts() {
+                    Some((e1, e2)) => {
+                        tasks.push(Task::Apply);
+                        tasks.push(Task::Visit(e2));
+                        tasks.push(Task::Visit(e1));
+                    },
+                    None => built.push(leaf(e)),
+                },
+                Task::Apply => {
+                    let e2 = built.pop().unwrap();
+                    let e1 = built.pop().unwrap();
+                    built.push(apply(e1, e2));
+                },
+            }
+        }
+        built.pop().unwrap()
+    }
+
+    /// Number of combinators and applications in the term.
+    fn count(&self) -> usize {
+        let mut count = 0;
+        let mut stack = vec![self];
+        while let Some(e) = stack.pop() {
+            count += 1;
+            if let Some((e1, e2)) = e.parts() {
+                stack.push(e2);
+                stack.push(e1);
+            }
+        }
+        count
+    }
+
+    fn clone_tree(&self) -> Self {
+        self.fold(Self::copy_leaf, Self::join)
+    }
+
+    fn eq_tree(&self, other: &Self) -> bool {
+        let mut stack = vec![(self, other)];
+        while let Some((a, b)) = stack.pop() {
+            match (a.parts(), b.parts()) {
+                (Some((f1, a1)), Some((f2, a2))) => {
+                    stack.push((a1, a2));
+                    stack.push((f1, f2));
+                },
+                (None, None) => if !a.same_leaf(b) { return false; },
+                _ => return false,
+            }
+        }
+        true
+    }
+
+    fn drop_tree(&mut self) {
+        let mut stack = Vec::new();
+        if let Some((e1, e2)) = self.take_parts() {
+            stack.push(e1);
+            stack.push(e2);
+        }
+        while let Some(mut e) = stack.pop() {
+            if let Some((e1, e2)) = e.take_parts() {
+                stack.push(e1);
+                stack.push(e2);
+            }
+        }
+    }
+}
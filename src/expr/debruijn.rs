@@ -0,0 +1,109 @@
+use std::mem;
+
+/// Nameless representation of a lambda term.
+///
+/// `Bound(0)` refers to the innermost enclosing `Lambda`. The name stored in
+/// `Lambda` is only a hint used when converting back to `LambdaExpr`, and is
+/// ignored when comparing terms, so alpha-equivalent terms compare equal.
+/// Dropping, cloning and comparing use an explicit stack rather than
+/// recursion, so that deep terms do not overflow the stack.
+#[derive(Debug)]
+pub enum DbExpr {
+    Free(String),
+    Bound(usize),
+    Apply(Box<DbExpr>, Box<DbExpr>),
+    Lambda(String, Box<DbExpr>),
+}
+
+impl DbExpr {
+    /// Application of `e1` to `e2`.
+    pub fn apply(e1: DbExpr, e2: DbExpr) -> DbExpr {
+        DbExpr::Apply(Box::new(e1), Box::new(e2))
+    }
+
+    /// Moves the term out, leaving a placeholder index.
+    pub fn take(&mut self) -> DbExpr {
+        mem::replace(self, DbExpr::Bound(0))
+    }
+
+    /// Moves the children of the term onto `stack`, leaving placeholders.
+    fn take_children(&mut self, stack: &mut Vec<DbExpr>) {
+        match self {
+            DbExpr::Free(_) | DbExpr::Bound(_) => {},
+            DbExpr::Apply(e1, e2) => {
+                stack.push(e1.take());
+                stack.push(e2.take());
+            },
+            DbExpr::Lambda(_, e) => stack.push(e.take()),
+        }
+    }
+}
+
+impl Drop for DbExpr {
+    fn drop(&mut self) {
+        let mut stack = Vec::new();
+        self.take_children(&mut stack);
+        while let Some(mut e) = stack.pop() {
+            e.take_children(&mut stack);
+        }
+    }
+}
+
+impl Clone for DbExpr {
+    fn clone(&self) -> DbExpr {
+        // post-order: children are built before their parent
+        enum Task<'a> {
+            Visit(&'a DbExpr),
+            Apply,
+            Lambda(&'a str),
+        }
+        let mut tasks = vec![Task::Visit(self)];
+        let mut built = Vec::new();
+        while let Some(task) = tasks.pop() {
+            match task {
+                Task::Visit(DbExpr::Free(v)) => built.push(DbExpr::Free(v.clone())),
+                Task::Visit(DbExpr::Bound(i)) => built.push(DbExpr::Bound(*i)),
+                Task::Visit(DbExpr::Apply(e1, e2)) => {
+                    tasks.push(Task::Apply);
+                    tasks.push(Task::Visit(e2));
+                    tasks.push(Task::Visit(e1));
+                },
+                Task::Visit(DbExpr::Lambda(v, e)) => {
+                    tasks.push(Task::Lambda(v));
+                    tasks.push(Task::Visit(e));
+                },
+                Task::Apply => {
+                    let e2 = built.pop().unwrap();
+                    let e1 = built.pop().unwrap();
+                    built.push(DbExpr::apply(e1, e2));
+                },
+                Task::Lambda(v) => {
+                    let e = built.pop().unwrap();
+                    built.push(DbExpr::Lambda(v.to_string(), Box::new(e)));
+                },
+            }
+        }
+        built.pop().unwrap()
+    }
+}
+
+impl PartialEq for DbExpr {
+    fn eq(&self, other: &DbExpr) -> bool {
+        let mut stack = vec![(self, other)];
+        while let Some(pair) = stack.pop() {
+            match pair {
+                (DbExpr::Free(v1), DbExpr::Free(v2)) => if v1 != v2 { return false; },
+                (DbExpr::Bound(i1), DbExpr::Bound(i2)) => if i1 != i2 { return false; },
+                (DbExpr::Apply(f1, a1), DbExpr::Apply(f2, a2)) => {
+                    stack.push((a1, a2));
+                    stack.push((f1, f2));
+                },
+                (DbExpr::Lambda(_, e1), DbExpr::Lambda(_, e2)) => stack.push((e1, e2)),
+                _ => return false,
+            }
+        }
+        true
+    }
+}
+
+impl Eq for DbExpr {}
@@ -1,24 +1,268 @@
-#[derive(Debug, PartialEq, Eq, Clone)]
+use expr::combinator::Combinator;
+use expr::lambda::ParseError;
+
+use std::mem;
+
+/// Iota term, applying `ι = λf f S K` to itself. Dropping, cloning,
+/// comparing and printing use an explicit stack rather than recursion, so
+/// that deep terms do not overflow the stack.
+#[derive(Debug)]
 pub enum IotaExpr {
     Apply(Box<IotaExpr>, Box<IotaExpr>),
     Iota,
 }
 
 impl IotaExpr {
+    /// Application of `e1` to `e2`.
+    pub fn apply(e1: IotaExpr, e2: IotaExpr) -> IotaExpr {
+        IotaExpr::Apply(Box::new(e1), Box::new(e2))
+    }
+
+    /// Moves the term out, leaving `ι` in its place.
+    pub fn take(&mut self) -> IotaExpr {
+        mem::replace(self, IotaExpr::Iota)
+    }
+
+    /// Number of iotas and applications in the term.
+    pub fn size(&self) -> usize {
+        self.count()
+    }
+
     fn append_to_string(&self, s: &mut String) {
-        match self {
-            &IotaExpr::Iota => { s.push('i'); },
-            &IotaExpr::Apply(ref e1, ref e2) => {
-                s.push('*');
-                e1.append_to_string(s);
-                e2.append_to_string(s);
-            },
+        // the prefix form is a pre-order walk
+        let mut stack = vec![self];
+        while let Some(e) = stack.pop() {
+            match e {
+                IotaExpr::Iota => s.push('i'),
+                IotaExpr::Apply(e1, e2) => {
+                    s.push('*');
+                    stack.push(e2);
+                    stack.push(e1);
+                },
+            }
         }
     }
 
+    #[allow(clippy::inherent_to_string_shadow_display)]
     pub fn to_string(&self) -> String {
         let mut ret = String::new();
         self.append_to_string(&mut ret);
         ret
     }
 }
+
+impl Combinator for IotaExpr {
+    fn parts(&self) -> Option<(&IotaExpr, &IotaExpr)> {
+        match self {
+            IotaExpr::Apply(e1, e2) => Some((e1, e2)),
+            IotaExpr::Iota => None,
+        }
+    }
+
+    fn take_parts(&mut self) -> Option<(IotaExpr, IotaExpr)> {
+        match self {
+            IotaExpr::Apply(e1, e2) => Some((e1.take(), e2.take())),
+            IotaExpr::Iota => None,
+        }
+    }
+
+    fn join(e1: IotaExpr, e2: IotaExpr) -> IotaExpr {
+        IotaExpr::apply(e1, e2)
+    }
+
+    fn copy_leaf(&self) -> IotaExpr {
+        IotaExpr::Iota
+    }
+
+    fn same_leaf(&self, _: &IotaExpr) -> bool {
+        true
+    }
+}
+
+impl Clone for IotaExpr {
+    fn clone(&self) -> IotaExpr {
+        self.clone_tree()
+    }
+}
+
+impl PartialEq for IotaExpr {
+    fn eq(&self, other: &IotaExpr) -> bool {
+        self.eq_tree(other)
+    }
+}
+
+impl Eq for IotaExpr {}
+
+impl Drop for IotaExpr {
+    fn drop(&mut self) {
+        self.drop_tree();
+    }
+}
+
+#[derive(Debug)]
+struct Lexer<'a> {
+    source: &'a str,
+    contents: &'a str,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+#[allow(clippy::upper_case_acronyms)]
+enum TokenType {
+    Star,
+    Iota,
+    LParen,
+    RParen,
+    Unknown,
+    EOF,
+}
+
+impl TokenType {
+    fn can_begin_expr(&self) -> bool {
+        match *self {
+            TokenType::Star => true,
+            TokenType::Iota => true,
+            TokenType::LParen => true,
+            TokenType::RParen => false,
+            TokenType::Unknown => false,
+            TokenType::EOF => false,
+        }
+    }
+
+    fn describe(&self) -> &'static str {
+        match *self {
+            TokenType::Star => "'*'",
+            TokenType::Iota => "'ι'",
+            TokenType::LParen => "'('",
+            TokenType::RParen => "')'",
+            TokenType::Unknown => "unknown character",
+            TokenType::EOF => "end of input",
+        }
+    }
+}
+
+impl<'a> Lexer<'a> {
+    fn new(contents: &str) -> Lexer<'_> {
+        Lexer { source: contents, contents: contents.trim_start() }
+    }
+
+    fn peek(&self) -> TokenType {
+        match self.contents.chars().next() {
+            Some('*') => TokenType::Star,
+            Some('i') | Some('ι') => TokenType::Iota,
+            Some('(') => TokenType::LParen,
+            Some(')') => TokenType::RParen,
+            Some(_) => TokenType::Unknown,
+            None => TokenType::EOF,
+        }
+    }
+
+    fn consume(&mut self) {
+        // find index of next code point
+        let index = self.contents.char_indices().nth(1).map(|x| x.0).unwrap_or(self.contents.len());
+        self.contents = self.contents[index..].trim_start();
+    }
+
+    fn offset(&self) -> usize {
+        self.source.len() - self.contents.len()
+    }
+
+    /// Error at the next token, which was not `expected`.
+    fn error(&self, expected: &str) -> ParseError {
+        let (offset, found) = match self.peek() {
+            TokenType::EOF => (self.source.trim_end().len(), TokenType::EOF.describe().to_string()),
+            TokenType::Unknown => (self.offset(), format!("character '{}'", self.contents.chars().next().unwrap())),
+            t => (self.offset(), t.describe().to_string()),
+        };
+        ParseError::new(self.source, offset, expected, found)
+    }
+}
+
+fn parse_atom(lex: &mut Lexer) -> Result<IotaExpr, ParseError> {
+    match lex.peek() {
+        TokenType::Iota => {
+            lex.consume();
+            Ok(IotaExpr::Iota)
+        },
+        TokenType::Star => {
+            lex.consume();
+            let e1 = parse_atom(lex)?;
+            let e2 = parse_atom(lex)?;
+            Ok(IotaExpr::apply(e1, e2))
+        },
+        TokenType::LParen => {
+            lex.consume();
+            let result = parse_expr(lex)?;
+            if lex.peek() != TokenType::RParen {
+                Err(lex.error("')'"))
+            } else {
+                lex.consume();
+                Ok(result)
+            }
+        },
+        _ => Err(lex.error("expression")),
+    }
+}
+
+fn parse_expr(lex: &mut Lexer) -> Result<IotaExpr, ParseError> {
+    let mut tree = parse_atom(lex)?;
+    while lex.peek().can_begin_expr() {
+        tree = IotaExpr::apply(tree, parse_atom(lex)?);
+    }
+    Ok(tree)
+}
+
+/// Parses either the prefix form produced by `IotaExpr::to_string` (`*i*ii`)
+/// or the applicative form produced by `Display` (`ι (ι ι)`).
+/// The two may be mixed; `i` and `ι` are interchangeable.
+pub fn parse(s: &str) -> Result<IotaExpr, ParseError> {
+    let mut lex: Lexer = Lexer::new(s);
+    let result = parse_expr(&mut lex)?;
+    if lex.peek() != TokenType::EOF {
+        return Err(lex.error("end of input"));
+    }
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use expr::iota::*;
+    use expr::iota::IotaExpr::*;
+
+    #[test]
+    fn single_iota() {
+        assert_eq!(Ok(Iota), parse("i"));
+        assert_eq!(Ok(Iota), parse(" ι "));
+    }
+
+    #[test]
+    fn prefix_form() {
+        assert_eq!(Ok(IotaExpr::apply(Iota, IotaExpr::apply(Iota, Iota))), parse("*i*ii"));
+        assert_eq!(Ok(IotaExpr::apply(IotaExpr::apply(Iota, Iota), Iota)), parse("**iii"));
+    }
+
+    #[test]
+    fn applicative_form() {
+        assert_eq!(Ok(IotaExpr::apply(Iota, IotaExpr::apply(Iota, Iota))), parse("ι (ι ι)"));
+        assert_eq!(Ok(IotaExpr::apply(IotaExpr::apply(Iota, Iota), Iota)), parse("ι ι ι"));
+    }
+
+    #[test]
+    fn round_trip() {
+        let e = IotaExpr::apply(IotaExpr::apply(Iota, IotaExpr::apply(Iota, Iota)), IotaExpr::apply(Iota, Iota));
+        assert_eq!(Ok(e.clone()), parse(&e.to_string()));
+        assert_eq!(Ok(e.clone()), parse(&format!("{}", e)));
+    }
+
+    fn error(s: &str) -> String {
+        parse(s).unwrap_err().to_string()
+    }
+
+    #[test]
+    fn errors() {
+        assert_eq!("1:3: expected expression, found end of input", error("*i"));
+        assert_eq!("1:5: expected ')', found end of input", error("(i i"));
+        assert_eq!("1:2: expected end of input, found ')'", error("i)"));
+        assert_eq!("1:3: expected expression, found character 'x'", error("*ix"));
+        assert_eq!("2:2: expected expression, found character 'x'", error("*i\n x"));
+    }
+}
@@ -1,77 +1,465 @@
+use expr::literal;
+use expr::literal::lambdas;
+use expr::literal::Encoding;
+
 use std::fmt;
+use std::mem;
 
-#[derive(Debug, PartialEq, Eq, Clone)]
+/// Lambda term. Dropping, cloning, comparing, measuring and displaying use an
+/// explicit stack rather than recursion, so that deep terms do not overflow the stack.
+#[derive(Debug, Eq)]
 pub enum LambdaExpr {
     Variable(String),
     Apply(Box<LambdaExpr>, Box<LambdaExpr>),
     Lambda(String, Box<LambdaExpr>),
 }
 
-struct ParenthesizedLeft<'a>(&'a LambdaExpr);
-struct ParenthesizedRight<'a>(&'a LambdaExpr);
+/// Contents of a `LambdaExpr` taken by value. Since `LambdaExpr`
+/// implements `Drop`, a `match` cannot move its fields out directly.
+#[derive(Debug)]
+pub enum Node {
+    Variable(String),
+    Apply(LambdaExpr, LambdaExpr),
+    Lambda(String, LambdaExpr),
+}
 
-impl <'a> fmt::Display for ParenthesizedLeft<'a> {
-    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
-        let &ParenthesizedLeft(expr) = self;
-        match expr {
-            &LambdaExpr::Lambda(_, _) => write!(f, "({})", expr),
-            _ => write!(f, "{}", expr),
+impl LambdaExpr {
+    /// Application of `e1` to `e2`.
+    pub fn apply(e1: LambdaExpr, e2: LambdaExpr) -> LambdaExpr {
+        LambdaExpr::Apply(Box::new(e1), Box::new(e2))
+    }
+
+    /// Moves the term out, leaving a placeholder variable.
+    pub fn take(&mut self) -> LambdaExpr {
+        mem::replace(self, LambdaExpr::Variable(String::new()))
+    }
+
+    pub fn into_node(mut self) -> Node {
+        match self {
+            LambdaExpr::Variable(ref mut v) => Node::Variable(mem::take(v)),
+            LambdaExpr::Apply(ref mut e1, ref mut e2) => Node::Apply(e1.take(), e2.take()),
+            LambdaExpr::Lambda(ref mut v, ref mut e) => Node::Lambda(mem::take(v), e.take()),
         }
     }
+
+    /// Moves the children of the term onto `stack`, leaving placeholders.
+    fn take_children(&mut self, stack: &mut Vec<LambdaExpr>) {
+        match self {
+            LambdaExpr::Variable(_) => {},
+            LambdaExpr::Apply(e1, e2) => {
+                stack.push(e1.take());
+                stack.push(e2.take());
+            },
+            LambdaExpr::Lambda(_, e) => stack.push(e.take()),
+        }
+    }
+
+    /// Number of variables, applications and lambdas in the term.
+    pub fn size(&self) -> usize {
+        let mut size = 0;
+        let mut stack = vec![self];
+        while let Some(e) = stack.pop() {
+            size += 1;
+            match e {
+                LambdaExpr::Variable(_) => {},
+                LambdaExpr::Apply(e1, e2) => {
+                    stack.push(e2);
+                    stack.push(e1);
+                },
+                LambdaExpr::Lambda(_, e) => stack.push(e),
+            }
+        }
+        size
+    }
 }
 
-impl <'a> fmt::Display for ParenthesizedRight<'a> {
-    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
-        let &ParenthesizedRight(expr) = self;
+impl Drop for LambdaExpr {
+    fn drop(&mut self) {
+        let mut stack = Vec::new();
+        self.take_children(&mut stack);
+        while let Some(mut e) = stack.pop() {
+            e.take_children(&mut stack);
+        }
+    }
+}
+
+impl Clone for LambdaExpr {
+    fn clone(&self) -> LambdaExpr {
+        // post-order: children are built before their parent
+        enum Task<'a> {
+            Visit(&'a LambdaExpr),
+            Apply,
+            Lambda(&'a str),
+        }
+        let mut tasks = vec![Task::Visit(self)];
+        let mut built = Vec::new();
+        while let Some(task) = tasks.pop() {
+            match task {
+                Task::Visit(LambdaExpr::Variable(v)) => built.push(LambdaExpr::Variable(v.clone())),
+                Task::Visit(LambdaExpr::Apply(e1, e2)) => {
+                    tasks.push(Task::Apply);
+                    tasks.push(Task::Visit(e2));
+                    tasks.push(Task::Visit(e1));
+                },
+                Task::Visit(LambdaExpr::Lambda(v, e)) => {
+                    tasks.push(Task::Lambda(v));
+                    tasks.push(Task::Visit(e));
+                },
+                Task::Apply => {
+                    let e2 = built.pop().unwrap();
+                    let e1 = built.pop().unwrap();
+                    built.push(LambdaExpr::Apply(Box::new(e1), Box::new(e2)));
+                },
+                Task::Lambda(v) => {
+                    let e = built.pop().unwrap();
+                    built.push(LambdaExpr::Lambda(v.to_string(), Box::new(e)));
+                },
+            }
+        }
+        built.pop().unwrap()
+    }
+}
+
+impl PartialEq for LambdaExpr {
+    fn eq(&self, other: &LambdaExpr) -> bool {
+        let mut stack = vec![(self, other)];
+        while let Some(pair) = stack.pop() {
+            match pair {
+                (LambdaExpr::Variable(v1), LambdaExpr::Variable(v2)) => if v1 != v2 { return false; },
+                (LambdaExpr::Apply(f1, a1), LambdaExpr::Apply(f2, a2)) => {
+                    stack.push((a1, a2));
+                    stack.push((f1, f2));
+                },
+                (LambdaExpr::Lambda(v1, e1), LambdaExpr::Lambda(v2, e2)) => {
+                    if v1 != v2 { return false; }
+                    stack.push((e1, e2));
+                },
+                _ => return false,
+            }
+        }
+        true
+    }
+}
+
+/// Writes `expr`, parenthesizing a lambda unless it is in `tail` position,
+/// i.e. nothing follows it that its body would otherwise swallow.
+fn write_expr(f: &mut fmt::Formatter, expr: &LambdaExpr, tail: bool) -> Result<(), fmt::Error> {
+    enum Task<'a> {
+        Expr(&'a LambdaExpr, bool),
+        Str(&'static str),
+    }
+    let mut tasks = vec![Task::Expr(expr, tail)];
+    while let Some(task) = tasks.pop() {
+        let (expr, tail) = match task {
+            Task::Str(s) => {
+                write!(f, "{}", s)?;
+                continue
+            },
+            Task::Expr(expr, tail) => (expr, tail),
+        };
+        // tasks are pushed in reverse order
         match expr {
-            &LambdaExpr::Apply(_, _) => write!(f, "({})", expr),
-            _ => write!(f, "{}", expr),
+            LambdaExpr::Variable(v) => write!(f, "{}", v)?,
+            LambdaExpr::Apply(e1, e2) => {
+                if let LambdaExpr::Apply(_, _) = **e2 {
+                    tasks.push(Task::Str(")"));
+                    tasks.push(Task::Expr(e2, true));
+                    tasks.push(Task::Str("("));
+                } else {
+                    tasks.push(Task::Expr(e2, tail));
+                }
+                tasks.push(Task::Str(" "));
+                if let LambdaExpr::Lambda(_, _) = **e1 {
+                    tasks.push(Task::Str(")"));
+                    tasks.push(Task::Expr(e1, true));
+                    tasks.push(Task::Str("("));
+                } else {
+                    tasks.push(Task::Expr(e1, false));
+                }
+            },
+            LambdaExpr::Lambda(v, e) => {
+                if !tail {
+                    tasks.push(Task::Str(")"));
+                    tasks.push(Task::Expr(expr, true));
+                    tasks.push(Task::Str("("));
+                } else if f.alternate() {
+                    // λx y z. body
+                    write!(f, "λ{}", v)?;
+                    let mut body = &**e;
+                    while let LambdaExpr::Lambda(ref v, ref e) = *body {
+                        write!(f, " {}", v)?;
+                        body = e;
+                    }
+                    write!(f, ". ")?;
+                    tasks.push(Task::Expr(body, true));
+                } else {
+                    write!(f, "λ{} ", v)?;
+                    tasks.push(Task::Expr(e, true));
+                }
+            },
         }
     }
+    Ok(())
 }
 
+/// The alternate form (`{:#}`) writes curried lambdas compactly,
+/// as `λx y. body` rather than `λx λy body`.
 impl fmt::Display for LambdaExpr {
     fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
-        match self {
-            &LambdaExpr::Variable(ref v) => write!(f, "{}", v),
-            &LambdaExpr::Apply(ref e1, ref e2) => write!(f, "{} {}", ParenthesizedLeft(&*e1), ParenthesizedRight(&*e2)),
-            &LambdaExpr::Lambda(ref v, ref e) => write!(f, "λ{} {}", v, e),
+        write_expr(f, self, true)
+    }
+}
+
+/// Error from `parse`, with the position of the offending token.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct ParseError {
+    /// Byte offset into the source.
+    pub offset: usize,
+    /// Line number, starting at 1.
+    pub line: usize,
+    /// Column in characters, starting at 1.
+    pub column: usize,
+    pub expected: String,
+    pub found: String,
+}
+
+impl ParseError {
+    pub(crate) fn new(source: &str, offset: usize, expected: &str, found: String) -> ParseError {
+        let before = &source[..offset];
+        let line_start = before.rfind('\n').map(|i| i + 1).unwrap_or(0);
+        ParseError {
+            offset,
+            line: before.matches('\n').count() + 1,
+            column: before[line_start..].chars().count() + 1,
+            expected: expected.to_string(),
+            found,
         }
     }
+
+    /// Formats the error followed by the offending line of `source`
+    /// and a caret pointing at the error position.
+    pub fn render(&self, source: &str) -> String {
+        let line_start = source[..self.offset].rfind('\n').map(|i| i + 1).unwrap_or(0);
+        let line_end = source[self.offset..].find('\n').map(|i| self.offset + i).unwrap_or(source.len());
+        let line = source[line_start..line_end].trim_end_matches('\r');
+        // keep tabs so the caret lines up
+        let padding: String = source[line_start..self.offset].chars()
+            .map(|c| if c == '\t' { '\t' } else { ' ' }).collect();
+        format!("{}\n{}\n{}^", self, line, padding)
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        write!(f, "{}:{}: expected {}, found {}", self.line, self.column, self.expected, self.found)
+    }
+}
+
+/// Comment found while parsing, kept for tools that reformat source files.
+/// Line comments run from `--` to the end of the line; block comments are
+/// delimited by `{-` and `-}` and may be nested.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Comment {
+    /// Byte offset into the source.
+    pub offset: usize,
+    /// Text of the comment, including delimiters.
+    pub text: String,
+}
+
+/// Length of the block comment at the start of `s`,
+/// or `None` if it is not terminated.
+fn block_comment_len(s: &str) -> Option<usize> {
+    let mut depth = 0;
+    let mut index = 0;
+    while index < s.len() {
+        let rest = &s[index..];
+        if rest.starts_with("{-") {
+            depth += 1;
+            index += 2;
+        } else if rest.starts_with("-}") {
+            depth -= 1;
+            index += 2;
+            if depth == 0 {
+                return Some(index);
+            }
+        } else {
+            index += rest.chars().next().unwrap().len_utf8();
+        }
+    }
+    None
+}
+
+/// Length of the line or block comment at the start of `s`, or `None` if
+/// there is none or it is not terminated. Also used by `expr::systemf`.
+pub(crate) fn comment_len(s: &str) -> Option<usize> {
+    if s.starts_with("--") {
+        Some(s.find('\n').unwrap_or(s.len()))
+    } else if s.starts_with("{-") {
+        block_comment_len(s)
+    } else {
+        None
+    }
 }
 
 #[derive(Debug)]
 struct Lexer<'a> {
+    source: &'a str,
     contents: &'a str,
+    comments: Option<Vec<Comment>>,
+    /// Whether a token at the start of a line ends the expression,
+    /// as in a top-level definition outside parentheses.
+    layout: bool,
+    /// Encoding of number and list literals.
+    encoding: Encoding,
 }
 
 #[derive(Debug, PartialEq, Eq)]
+#[allow(clippy::upper_case_acronyms)]
 enum TokenType {
     Ident,
+    Number,
     LParen,
     RParen,
     Lambda,
+    Dot,
+    Let,
+    LetRec,
+    In,
+    Equals,
+    Import,
+    Str,
+    LBracket,
+    RBracket,
+    Comma,
+    UnterminatedComment,
+    UnterminatedStr,
     EOF,
 }
 
 impl TokenType {
     fn can_begin_expr(&self) -> bool {
         match *self {
-            TokenType::Ident => true,
+            TokenType::Ident | TokenType::Number => true,
             TokenType::LParen => true,
             TokenType::RParen => false,
             TokenType::Lambda => true,
+            TokenType::Dot => false,
+            TokenType::Let | TokenType::LetRec => true,
+            TokenType::In | TokenType::Equals => false,
+            TokenType::Import | TokenType::Str => false,
+            TokenType::LBracket => true,
+            TokenType::RBracket | TokenType::Comma => false,
+            TokenType::UnterminatedComment | TokenType::UnterminatedStr => false,
             TokenType::EOF => false,
         }
     }
+
+    fn describe(&self) -> &'static str {
+        match *self {
+            TokenType::Ident => "identifier",
+            TokenType::Number => "number",
+            TokenType::LParen => "'('",
+            TokenType::RParen => "')'",
+            TokenType::Lambda => "'λ'",
+            TokenType::Dot => "'.'",
+            TokenType::Let => "'let'",
+            TokenType::LetRec => "'letrec'",
+            TokenType::In => "'in'",
+            TokenType::Equals => "'='",
+            TokenType::Import => "'import'",
+            TokenType::Str => "string",
+            TokenType::LBracket => "'['",
+            TokenType::RBracket => "']'",
+            TokenType::Comma => "','",
+            TokenType::UnterminatedComment => "unterminated comment",
+            TokenType::UnterminatedStr => "unterminated string",
+            TokenType::EOF => "end of input",
+        }
+    }
 }
 
 impl<'a> Lexer<'a> {
-    fn new(contents: &str) -> Lexer {
-        Lexer { contents: contents.trim_left() }
+    fn new(contents: &str, keep_comments: bool) -> Lexer<'_> {
+        let mut lex = Lexer {
+            source: contents,
+            contents,
+            comments: if keep_comments { Some(Vec::new()) } else { None },
+            layout: false,
+            encoding: Encoding::Church,
+        };
+        lex.skip_whitespace();
+        lex
+    }
+
+    /// Skips whitespace and comments. Stops at the start of an
+    /// unterminated block comment.
+    fn skip_whitespace(&mut self) {
+        loop {
+            self.contents = self.contents.trim_start();
+            let len = match comment_len(self.contents) {
+                Some(len) => len,
+                None => return,
+            };
+            let offset = self.offset();
+            if let Some(ref mut comments) = self.comments {
+                comments.push(Comment { offset, text: self.contents[..len].to_string() });
+            }
+            self.contents = &self.contents[len..];
+        }
+    }
+
+    /// Error at the next token, which was not `expected`.
+    fn error(&self, expected: &str) -> ParseError {
+        let (offset, found) = match self.peek() {
+            TokenType::EOF => (self.source.trim_end().len(), TokenType::EOF.describe().to_string()),
+            TokenType::Ident => {
+                let mut copy = Lexer { source: self.source, contents: self.contents, comments: None, layout: false, encoding: self.encoding };
+                (self.offset(), format!("identifier '{}'", copy.consume().unwrap()))
+            },
+            t => (self.offset(), t.describe().to_string()),
+        };
+        ParseError::new(self.source, offset, expected, found)
+    }
+
+    /// Number of identifiers before the next token if that is a dot, else 0.
+    fn params_before_dot(&self) -> usize {
+        let mut copy = Lexer { source: self.source, contents: self.contents, comments: None, layout: false, encoding: self.encoding };
+        let mut count = 0;
+        while copy.peek() == TokenType::Ident {
+            copy.consume();
+            count += 1;
+        }
+        if copy.peek() == TokenType::Dot { count } else { 0 }
+    }
+
+    /// Whether the next tokens are a name and parameters followed by `=`,
+    /// i.e. the start of a top-level definition.
+    fn at_definition(&self) -> bool {
+        let mut copy = Lexer { source: self.source, contents: self.contents, comments: None, layout: false, encoding: self.encoding };
+        if copy.peek() != TokenType::Ident {
+            return false;
+        }
+        while copy.peek() == TokenType::Ident {
+            copy.consume();
+        }
+        copy.peek() == TokenType::Equals
+    }
+
+    /// Whether the next token is at the very start of a line.
+    fn at_line_start(&self) -> bool {
+        let before = &self.source[..self.offset()];
+        before.is_empty() || before.ends_with('\n')
+    }
+
+    fn offset(&self) -> usize {
+        self.source.len() - self.contents.len()
     }
 
     fn peek(&self) -> TokenType {
+        if self.contents.starts_with("{-") {
+            // otherwise skipped
+            return TokenType::UnterminatedComment;
+        }
         let ch = self.contents.chars().next();
         if let Some(ch) = ch {
             match ch {
@@ -79,72 +467,310 @@ impl<'a> Lexer<'a> {
                 ')' => TokenType::RParen,
                 '\\' => TokenType::Lambda,
                 'λ' => TokenType::Lambda,
-                _ => TokenType::Ident,
+                '.' => TokenType::Dot,
+                '=' => TokenType::Equals,
+                '[' => TokenType::LBracket,
+                ']' => TokenType::RBracket,
+                ',' => TokenType::Comma,
+                '"' => if self.contents[1..].contains('"') { TokenType::Str } else { TokenType::UnterminatedStr },
+                _ => match &self.contents[..self.ident_len()] {
+                    "let" => TokenType::Let,
+                    "letrec" => TokenType::LetRec,
+                    "in" => TokenType::In,
+                    "import" => TokenType::Import,
+                    word if word.bytes().all(|b| b.is_ascii_digit()) => TokenType::Number,
+                    _ => TokenType::Ident,
+                },
             }
         } else {
             TokenType::EOF
         }
     }
 
+    /// Length of the identifier at the start of the input.
+    fn ident_len(&self) -> usize {
+        // find index of first non-word character
+        let contents = self.contents;
+        contents.char_indices().find(|p| {
+            let c = p.1;
+            c == '(' || c == ')' || c == '\\' || c == 'λ' || c == '.' || c == '=' || c == '"'
+                || c == '[' || c == ']' || c == ',' || c.is_whitespace()
+                || contents[p.0..].starts_with("--") || contents[p.0..].starts_with("{-")
+        }).map(|x| x.0).unwrap_or(contents.len())
+    }
+
     fn consume(&mut self) -> Option<&str> {
         match self.peek() {
-            TokenType::EOF => None,
-            TokenType::LParen | TokenType::RParen | TokenType::Lambda => {
+            TokenType::EOF | TokenType::UnterminatedComment | TokenType::UnterminatedStr => None,
+            TokenType::LParen | TokenType::RParen | TokenType::Lambda | TokenType::Dot | TokenType::Equals
+                    | TokenType::LBracket | TokenType::RBracket | TokenType::Comma => {
                 // find index of next code point
                 let index = self.contents.char_indices().nth(1).map(|x| x.0).unwrap_or(self.contents.len());
-                self.contents = &self.contents[index..].trim_left();
+                self.contents = &self.contents[index..];
+                self.skip_whitespace();
                 None
             },
-            TokenType::Ident => {
-                // find index of first non-word character
-                let index = self.contents.char_indices().find(|p| {
-                    let c = p.1;
-                    c == '(' || c == ')' || c == '\\' || c == 'λ' || c.is_whitespace()
-                }).map(|x| x.0).unwrap_or(self.contents.len());
-                let (ret, rest) = self.contents.split_at(index);
-                self.contents = rest.trim_left();
+            TokenType::Str => {
+                // contents without the quotes
+                let len = self.contents[1..].find('"').unwrap();
+                let (ret, rest) = self.contents.split_at(len + 2);
+                self.contents = rest;
+                self.skip_whitespace();
+                Some(&ret[1..len + 1])
+            },
+            TokenType::Ident | TokenType::Number
+                    | TokenType::Let | TokenType::LetRec | TokenType::In | TokenType::Import => {
+                let (ret, rest) = self.contents.split_at(self.ident_len());
+                self.contents = rest;
+                self.skip_whitespace();
                 Some(ret)
             },
         }
     }
 }
 
-fn parse_atom(lex: &mut Lexer) -> Result<LambdaExpr, String> {
+/// Fixpoint combinator used to desugar `letrec`: `λf (λx f (x x)) (λx f (x x))`.
+pub fn fix() -> LambdaExpr {
+    let half = LambdaExpr::Lambda("x".to_string(), Box::new(LambdaExpr::Apply(
+        Box::new(LambdaExpr::Variable("f".to_string())),
+        Box::new(LambdaExpr::Apply(
+            Box::new(LambdaExpr::Variable("x".to_string())),
+            Box::new(LambdaExpr::Variable("x".to_string())))))));
+    LambdaExpr::Lambda("f".to_string(), Box::new(LambdaExpr::Apply(Box::new(half.clone()), Box::new(half))))
+}
+
+/// `(λname body) value`, with `value` passed through the fixpoint
+/// combinator first if `recursive`.
+fn bind(name: String, value: LambdaExpr, body: LambdaExpr, recursive: bool) -> LambdaExpr {
+    let value = if recursive {
+        LambdaExpr::Apply(Box::new(fix()), Box::new(LambdaExpr::Lambda(name.clone(), Box::new(value))))
+    } else {
+        value
+    };
+    LambdaExpr::Apply(Box::new(LambdaExpr::Lambda(name, Box::new(body))), Box::new(value))
+}
+
+fn parse_atom(lex: &mut Lexer) -> Result<LambdaExpr, ParseError> {
     match lex.peek() {
         TokenType::Ident => Ok(LambdaExpr::Variable(lex.consume().unwrap().to_string())),
         TokenType::LParen => {
             lex.consume();
+            let layout = lex.layout;
+            lex.layout = false;
             let result = parse_expr(lex)?;
+            lex.layout = layout;
             if lex.peek() != TokenType::RParen {
-                Err(format!("expected RParen, got {:?}", lex.peek()))
+                Err(lex.error(TokenType::RParen.describe()))
             } else {
                 lex.consume();
                 Ok(result)
             }
         },
+        TokenType::Number => {
+            let offset = lex.offset();
+            let text = lex.consume().unwrap().to_string();
+            match text.parse() {
+                Ok(n) => Ok(literal::numeral(n, lex.encoding)),
+                Err(_) => Err(ParseError::new(lex.source, offset, "number below 2^64", format!("'{}'", text))),
+            }
+        },
+        TokenType::LBracket => {
+            lex.consume();
+            let layout = lex.layout;
+            lex.layout = false;
+            let mut items = Vec::new();
+            if lex.peek() != TokenType::RBracket {
+                items.push(parse_expr(lex)?);
+                while lex.peek() == TokenType::Comma {
+                    lex.consume();
+                    items.push(parse_expr(lex)?);
+                }
+            }
+            lex.layout = layout;
+            if lex.peek() != TokenType::RBracket {
+                return Err(lex.error("',' or ']'"));
+            }
+            lex.consume();
+            Ok(literal::list(items, lex.encoding))
+        },
         TokenType::Lambda => {
+            lex.consume();
+            // λx y. body and λx.y. body, or a single parameter without a dot
+            let mut params = Vec::new();
+            loop {
+                let count = lex.params_before_dot();
+                if count == 0 { break; }
+                for _ in 0..count {
+                    params.push(lex.consume().unwrap().to_string());
+                }
+                lex.consume();
+            }
+            if params.is_empty() {
+                if lex.peek() != TokenType::Ident {
+                    return Err(lex.error("parameter name after 'λ'"));
+                }
+                params.push(lex.consume().unwrap().to_string());
+            }
+            let body = parse_expr(lex)?;
+            Ok(lambdas(params, body))
+        },
+        TokenType::Let | TokenType::LetRec => {
+            // let name params = value in body, desugared to (λname body) (λparams value);
+            // letrec passes the value through the fixpoint combinator first
+            let keyword = lex.peek();
             lex.consume();
             if lex.peek() != TokenType::Ident {
-                return Err(format!("expected Ident after Lambda, got {:?}", lex.peek()));
+                return Err(lex.error(&format!("name after {}", keyword.describe())));
+            }
+            let name = lex.consume().unwrap().to_string();
+            let mut params = Vec::new();
+            while lex.peek() == TokenType::Ident {
+                params.push(lex.consume().unwrap().to_string());
+            }
+            if lex.peek() != TokenType::Equals {
+                return Err(lex.error(TokenType::Equals.describe()));
             }
-            let param = lex.consume().unwrap().to_string();
-            Ok(LambdaExpr::Lambda(param, Box::new(parse_expr(lex)?)))
+            lex.consume();
+            let value = lambdas(params, parse_expr(lex)?);
+            if lex.peek() != TokenType::In {
+                return Err(lex.error(TokenType::In.describe()));
+            }
+            lex.consume();
+            let body = parse_expr(lex)?;
+            Ok(bind(name, value, body, keyword == TokenType::LetRec))
         },
-        _ => Err(format!("expected expression, got {:?}", lex.peek()))
+        _ => Err(lex.error("expression"))
     }
 }
 
-fn parse_expr(lex: &mut Lexer) -> Result<LambdaExpr, String> {
+fn parse_expr(lex: &mut Lexer) -> Result<LambdaExpr, ParseError> {
     let mut tree = parse_atom(lex)?;
-    while lex.peek().can_begin_expr() {
+    while lex.peek().can_begin_expr() && !(lex.layout && lex.at_line_start()) {
         tree = LambdaExpr::Apply(Box::new(tree), Box::new(parse_atom(lex)?));
     }
     Ok(tree)
 }
 
-pub fn parse(s: &str) -> Result<LambdaExpr, String> {
-    let mut lex: Lexer = Lexer::new(s);
-    parse_expr(&mut lex)
+/// Top-level definition `name params = value`; the parameters
+/// are already folded into `value`.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Definition {
+    pub name: String,
+    pub value: LambdaExpr,
+}
+
+/// `import "path"` directive.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Import {
+    /// Byte offset into the source.
+    pub offset: usize,
+    pub path: String,
+}
+
+/// Contents of a source file: imports and definitions in any order,
+/// optionally followed by an expression. A definition continues until
+/// a line that starts without indentation, outside parentheses.
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct Program {
+    pub imports: Vec<Import>,
+    pub definitions: Vec<Definition>,
+    pub body: Option<LambdaExpr>,
+}
+
+/// Wraps `body` in a binding for each definition it uses, directly or through
+/// other definitions. A definition may refer to itself, in which case it is bound
+/// as with `letrec`, and to definitions before it; later ones shadow earlier ones.
+pub fn bind_definitions(definitions: &[Definition], body: LambdaExpr) -> LambdaExpr {
+    definitions.iter().rev().fold(body, |body, def| {
+        if body.contains(&def.name) {
+            let recursive = def.value.contains(&def.name);
+            bind(def.name.clone(), def.value.clone(), body, recursive)
+        } else {
+            body
+        }
+    })
+}
+
+impl Program {
+    /// The body with the definitions it uses bound, or `None` if there is no body.
+    pub fn into_expr(self) -> Option<LambdaExpr> {
+        let definitions = self.definitions;
+        self.body.map(|body| bind_definitions(&definitions, body))
+    }
+}
+
+/// Parses an expression, optionally preceded by definitions, which are bound
+/// with `bind_definitions`. Use `parse_program` for sources with imports.
+pub fn parse(s: &str) -> Result<LambdaExpr, ParseError> {
+    let mut lex: Lexer = Lexer::new(s, false);
+    parse_all(&mut lex)
+}
+
+/// Like `parse`, but also returns the comments in source order.
+pub fn parse_with_comments(s: &str) -> Result<(LambdaExpr, Vec<Comment>), ParseError> {
+    let mut lex: Lexer = Lexer::new(s, true);
+    let result = parse_all(&mut lex)?;
+    Ok((result, lex.comments.unwrap()))
+}
+
+/// Parses a program without binding its definitions or resolving its imports.
+/// Number and list literals use the Church encoding.
+pub fn parse_program(s: &str) -> Result<Program, ParseError> {
+    parse_program_with(s, Encoding::Church)
+}
+
+/// Like `parse_program`, with literals in the given encoding.
+pub fn parse_program_with(s: &str, encoding: Encoding) -> Result<Program, ParseError> {
+    let mut lex: Lexer = Lexer::new(s, false);
+    lex.encoding = encoding;
+    parse_top_level(&mut lex)
+}
+
+fn parse_all(lex: &mut Lexer) -> Result<LambdaExpr, ParseError> {
+    let program = parse_top_level(lex)?;
+    if let Some(import) = program.imports.first() {
+        return Err(ParseError::new(lex.source, import.offset, "definition or expression",
+            TokenType::Import.describe().to_string()));
+    }
+    match program.into_expr() {
+        Some(e) => Ok(e),
+        None => Err(lex.error("expression")),
+    }
+}
+
+fn parse_top_level(lex: &mut Lexer) -> Result<Program, ParseError> {
+    let mut program = Program::default();
+    loop {
+        if lex.peek() == TokenType::Import {
+            let offset = lex.offset();
+            lex.consume();
+            if lex.peek() != TokenType::Str {
+                return Err(lex.error("file name after 'import'"));
+            }
+            let path = lex.consume().unwrap().to_string();
+            program.imports.push(Import { offset, path });
+        } else if lex.at_definition() {
+            let name = lex.consume().unwrap().to_string();
+            let mut params = Vec::new();
+            while lex.peek() == TokenType::Ident {
+                params.push(lex.consume().unwrap().to_string());
+            }
+            lex.consume();
+            lex.layout = true;
+            let value = lambdas(params, parse_expr(lex)?);
+            lex.layout = false;
+            program.definitions.push(Definition { name, value });
+        } else {
+            break;
+        }
+    }
+    if lex.peek() != TokenType::EOF {
+        program.body = Some(parse_expr(lex)?);
+        if lex.peek() != TokenType::EOF {
+            return Err(lex.error(TokenType::EOF.describe()));
+        }
+    }
+    Ok(program)
 }
 
 #[cfg(test)]
@@ -160,6 +786,14 @@ mod tests {
         "y".to_string()
     }
 
+    #[test]
+    fn size_deep() {
+        // deeper than the recursion the default test thread stack allows
+        let n = 300000;
+        let deep = (0..n).fold(Variable(x()), |e, _| Lambda(y(), Box::new(LambdaExpr::apply(Variable(y()), e))));
+        assert_eq!(3 * n + 1, deep.size());
+    }
+
     #[test]
     fn single_variable() {
         assert_eq!(Ok(Variable(x())), parse("x"));
@@ -228,4 +862,185 @@ mod tests {
     fn display_church_add() {
         assert_eq!("λm λn λf λx m f (n f x)", format!("{}", parse("\\m \\n \\f \\x m f (n f x)").unwrap()));
     }
+
+    #[test]
+    fn error_unclosed_paren() {
+        let err = parse("(x y").unwrap_err();
+        assert_eq!((4, 1, 5), (err.offset, err.line, err.column));
+        assert_eq!("1:5: expected ')', found end of input", format!("{}", err));
+    }
+
+    #[test]
+    fn error_position_multiline() {
+        let src = "(\\x\n  x ))\n";
+        let err = parse(src).unwrap_err();
+        assert_eq!((src.find("))").unwrap() + 1, 2, 6), (err.offset, err.line, err.column));
+        assert_eq!("2:6: expected end of input, found ')'\n  x ))\n     ^", err.render(src));
+    }
+
+    #[test]
+    fn error_lambda_parameter() {
+        let err = parse("λ(x) x").unwrap_err();
+        assert_eq!((2, 1, 2), (err.offset, err.line, err.column));
+        assert_eq!("parameter name after 'λ'", err.expected);
+        assert_eq!("'('", err.found);
+    }
+
+    #[test]
+    fn error_render_tabs() {
+        let src = "\tx\t)";
+        assert_eq!("1:4: expected end of input, found ')'\n\tx\t)\n\t \t^", parse(src).unwrap_err().render(src));
+    }
+
+    #[test]
+    fn line_comment() {
+        assert_eq!(parse("\\x x").unwrap(), parse("-- identity\n\\x -- parameter\n  x -- body").unwrap());
+        assert_eq!(Ok(Variable("y-comb".to_string())), parse("y-comb--comment"));
+    }
+
+    #[test]
+    fn block_comment() {
+        assert_eq!(parse("x y").unwrap(), parse("x {- nested {- block -} comment -} y").unwrap());
+        assert_eq!(Ok(Apply(Box::new(Variable(x())), Box::new(Variable(y())))), parse("x{--}y"));
+    }
+
+    #[test]
+    fn unterminated_comment() {
+        let err = parse("x {- {- -} y").unwrap_err();
+        assert_eq!((2, "unterminated comment".to_string()), (err.offset, err.found));
+    }
+
+    #[test]
+    fn keep_comments() {
+        let (e, comments) = parse_with_comments("{- id -} \\x x -- done").unwrap();
+        assert_eq!(parse("\\x x").unwrap(), e);
+        assert_eq!(vec![
+                Comment { offset: 0, text: "{- id -}".to_string() },
+                Comment { offset: 14, text: "-- done".to_string() }],
+            comments);
+    }
+
+    #[test]
+    fn dot_parameters() {
+        let add = parse("\\m \\n \\f \\x m f (n f x)").unwrap();
+        assert_eq!(add, parse("\\m n f x. m f (n f x)").unwrap());
+        assert_eq!(add, parse("λm.n.f.x. m f (n f x)").unwrap());
+        assert_eq!(add, parse("λm n. λf.x.m f (n f x)").unwrap());
+    }
+
+    #[test]
+    fn dot_body_variable() {
+        assert_eq!(parse("\\x \\y y").unwrap(), parse("λx.λy.y").unwrap());
+        assert_eq!(parse("\\x y").unwrap(), parse("λx.y").unwrap());
+    }
+
+    #[test]
+    fn dot_errors() {
+        assert_eq!("'.'", parse("λ. x").unwrap_err().found);
+        assert_eq!("end of input", parse("λx y.").unwrap_err().found);
+    }
+
+    #[test]
+    fn display_compact() {
+        let e = parse("\\m \\n \\f \\x m f (n f x)").unwrap();
+        assert_eq!("λm n f x. m f (n f x)", format!("{:#}", e));
+        assert_eq!(e, parse(&format!("{:#}", e)).unwrap());
+        assert_eq!("(λx. x) λy z. z", format!("{:#}", parse("(\\x x) (\\y \\z z)").unwrap()));
+    }
+
+    #[test]
+    fn display_lambda_argument() {
+        assert_eq!("f (λx x) y", format!("{}", parse("f (\\x x) y").unwrap()));
+        assert_eq!("f λx x", format!("{}", parse("f (\\x x)").unwrap()));
+    }
+
+    #[test]
+    fn let_binding() {
+        assert_eq!(parse("(\\id id y) (\\x x)").unwrap(), parse("let id = \\x x in id y").unwrap());
+        assert_eq!(parse("(\\k k) (\\x \\y x)").unwrap(), parse("let k x y = x in k").unwrap());
+    }
+
+    #[test]
+    fn let_nested() {
+        let e = parse("let a = x in\nlet b = a a in\nb").unwrap();
+        assert_eq!(parse("(\\a (\\b b) (a a)) x").unwrap(), e);
+    }
+
+    #[test]
+    fn letrec_binding() {
+        let e = parse("letrec f n = f n in f").unwrap();
+        assert_eq!(Apply(Box::new(parse("\\f f").unwrap()), Box::new(Apply(Box::new(fix()), Box::new(parse("\\f \\n f n").unwrap())))), e);
+    }
+
+    #[test]
+    fn let_errors() {
+        assert_eq!(("'='".to_string(), "'in'".to_string()), { let e = parse("let x y in z").unwrap_err(); (e.expected, e.found) });
+        assert_eq!(("'in'".to_string(), "end of input".to_string()), { let e = parse("let x = y").unwrap_err(); (e.expected, e.found) });
+        assert_eq!("name after 'letrec'", parse("letrec = x in x").unwrap_err().expected);
+    }
+
+    #[test]
+    fn definitions() {
+        let e = parse("id x = x\nk x y = x\nk id").unwrap();
+        assert_eq!(parse("(\\k k id) (\\x \\y x)").unwrap().replace("id", &parse("\\x x").unwrap()), e.clone().beta().0);
+        assert_eq!(parse("(\\id (\\k k id) (\\x \\y x)) (\\x x)").unwrap(), e);
+    }
+
+    #[test]
+    fn definitions_unused() {
+        assert_eq!(parse("(\\b b) (\\x x)").unwrap(), parse("a = \\x x\nb = \\x x\nb").unwrap());
+        assert_eq!(parse("(\\a (\\b b) a) (\\x x)").unwrap(), parse("a = \\x x\nb = a\nb").unwrap());
+    }
+
+    #[test]
+    fn definitions_recursive() {
+        let e = parse("f n = f n\nf").unwrap();
+        assert_eq!(parse("letrec f n = f n in f").unwrap(), e);
+    }
+
+    #[test]
+    fn program_imports() {
+        let program = parse_program("import \"lib.lambda\"\n-- main\nx = y\nx x").unwrap();
+        assert_eq!(vec![Import { offset: 0, path: "lib.lambda".to_string() }], program.imports);
+        assert_eq!(vec![Definition { name: "x".to_string(), value: Variable(y()) }], program.definitions);
+        assert_eq!(Some(parse("x x").unwrap()), program.body);
+        assert_eq!(None, parse_program("x = y").unwrap().body);
+    }
+
+    #[test]
+    fn program_errors() {
+        let err = parse("x = y\nimport \"lib\"\nx").unwrap_err();
+        assert_eq!((2, "'import'".to_string()), (err.line, err.found));
+        assert_eq!("expression", parse("x = y").unwrap_err().expected);
+        assert_eq!("unterminated string", parse_program("import \"lib").unwrap_err().found);
+        assert_eq!("end of input", parse("(x)\ny = x").unwrap_err().expected);
+        assert_eq!("'='", parse("x = y\n z = y\nz").unwrap_err().found);
+    }
+
+    #[test]
+    fn definitions_layout() {
+        let program = parse_program("f = a\n  b -- comment\n\t(c\nd)\ng = f\ng\n  h\nk").unwrap();
+        assert_eq!(parse("a b (c d)").unwrap(), program.definitions[0].value);
+        assert_eq!(Some(parse("g h k").unwrap()), program.body);
+    }
+
+    #[test]
+    fn number_literals() {
+        assert_eq!(parse("\\f \\x f (f x)").unwrap(), parse("2").unwrap());
+        assert_eq!(parse("succ (\\f \\x x) a1").unwrap(), parse("succ 0 a1").unwrap());
+        let program = parse_program_with("x = 1", Encoding::Scott).unwrap();
+        assert_eq!(parse("\\s \\z s (\\s \\z z)").unwrap(), program.definitions[0].value);
+        assert_eq!("number below 2^64", parse("f 18446744073709551616").unwrap_err().expected);
+    }
+
+    #[test]
+    fn list_literals() {
+        assert_eq!(parse("\\c \\n n").unwrap(), parse("[]").unwrap());
+        assert_eq!(parse("\\c \\n c (f x) (c (\\f \\x x) n)").unwrap(), parse("[f x, 0]").unwrap());
+        assert_eq!(parse("\\c \\n c (\\c \\n n) n").unwrap(), parse("[[]]").unwrap());
+        let program = parse_program("x = [a,\nb]\ny").unwrap();
+        assert_eq!(parse("[a, b]").unwrap(), program.definitions[0].value);
+        assert_eq!("',' or ']'", parse("[a b").unwrap_err().expected);
+        assert_eq!("expression", parse("[a,]").unwrap_err().expected);
+    }
 }
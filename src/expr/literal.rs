@@ -0,0 +1,142 @@
+use expr::lambda::LambdaExpr;
+
+use std::fmt;
+use std::str::FromStr;
+
+/// How data such as numbers and lists is represented as lambda terms.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Encoding {
+    /// Data is its own fold: `n = λf λx f (... (f x))`, `[a, b] = λc λn c a (c b n)`.
+    Church,
+    /// Data is its own case analysis: `n + 1 = λs λz s n`, `a : l = λc λn c a l`.
+    Scott,
+}
+
+impl Encoding {
+    pub fn all() -> &'static [Encoding] {
+        &[Encoding::Church, Encoding::Scott]
+    }
+
+    pub fn name(&self) -> &'static str {
+        match *self {
+            Encoding::Church => "church",
+            Encoding::Scott => "scott",
+        }
+    }
+}
+
+impl fmt::Display for Encoding {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        write!(f, "{}", self.name())
+    }
+}
+
+impl FromStr for Encoding {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Encoding, String> {
+        Encoding::all().iter().cloned().find(|enc| enc.name() == s)
+            .ok_or_else(|| format!("unknown encoding: {}", s))
+    }
+}
+
+pub(crate) fn var(name: &str) -> LambdaExpr {
+    LambdaExpr::Variable(name.to_string())
+}
+
+pub(crate) fn lam(name: &str, body: LambdaExpr) -> LambdaExpr {
+    LambdaExpr::Lambda(name.to_string(), Box::new(body))
+}
+
+/// `λp1 .. λpn body` for the parameters `p1 .. pn`.
+pub(crate) fn lambdas(params: Vec<String>, body: LambdaExpr) -> LambdaExpr {
+    params.into_iter().rev().fold(body, |e, v| LambdaExpr::Lambda(v, Box::new(e)))
+}
+
+/// `base` with `'` appended until it is free in none of `exprs`.
+pub(crate) fn fresh(base: &str, exprs: &[&LambdaExpr]) -> String {
+    let mut name = base.to_string();
+    while exprs.iter().any(|e| e.contains(&name)) {
+        name.push('\'');
+    }
+    name
+}
+
+/// The numeral for `n`.
+pub fn numeral(n: u64, encoding: Encoding) -> LambdaExpr {
+    match encoding {
+        Encoding::Church => {
+            let mut body = var("x");
+            for _ in 0..n {
+                body = LambdaExpr::apply(var("f"), body);
+            }
+            lam("f", lam("x", body))
+        },
+        Encoding::Scott => {
+            let mut e = lam("s", lam("z", var("z")));
+            for _ in 0..n {
+                e = lam("s", lam("z", LambdaExpr::apply(var("s"), e)));
+            }
+            e
+        },
+    }
+}
+
+/// The list of `items`, with binders renamed so as not to capture their free variables.
+pub fn list(items: Vec<LambdaExpr>, encoding: Encoding) -> LambdaExpr {
+    let (c, n) = {
+        let refs: Vec<&LambdaExpr> = items.iter().collect();
+        (fresh("c", &refs), fresh("n", &refs))
+    };
+    match encoding {
+        Encoding::Church => {
+            let body = items.into_iter().rev().fold(var(&n), |tail, item| LambdaExpr::apply(LambdaExpr::apply(var(&c), item), tail));
+            lam(&c, lam(&n, body))
+        },
+        Encoding::Scott => {
+            items.into_iter().rev().fold(lam(&c, lam(&n, var(&n))), |tail, item| {
+                lam(&c, lam(&n, LambdaExpr::apply(LambdaExpr::apply(var(&c), item), tail)))
+            })
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use expr::literal::*;
+    use expr::lambda::parse;
+
+    #[test]
+    fn church_numerals() {
+        assert_eq!(parse("\\f \\x x").unwrap(), numeral(0, Encoding::Church));
+        assert_eq!(parse("\\f \\x f (f (f x))").unwrap(), numeral(3, Encoding::Church));
+    }
+
+    #[test]
+    fn scott_numerals() {
+        assert_eq!(parse("\\s \\z z").unwrap(), numeral(0, Encoding::Scott));
+        assert_eq!(parse("\\s \\z s (\\s \\z s (\\s \\z z))").unwrap(), numeral(2, Encoding::Scott));
+    }
+
+    #[test]
+    fn lists() {
+        let items = vec![parse("a").unwrap(), parse("b").unwrap()];
+        assert_eq!(parse("\\c \\n c a (c b n)").unwrap(), list(items.clone(), Encoding::Church));
+        assert_eq!(parse("\\c \\n c a (\\c \\n c b (\\c \\n n))").unwrap(), list(items, Encoding::Scott));
+        assert_eq!(parse("\\c \\n n").unwrap(), list(Vec::new(), Encoding::Church));
+    }
+
+    #[test]
+    fn list_capture() {
+        let items = vec![parse("c n").unwrap(), parse("\\c c").unwrap()];
+        assert_eq!(parse("\\c' \\n' c' (c n) (c' (\\c c) n')").unwrap(), list(items, Encoding::Church));
+    }
+
+    #[test]
+    fn names() {
+        for &enc in Encoding::all() {
+            assert_eq!(Ok(enc), enc.name().parse());
+        }
+        assert_eq!(Err("unknown encoding: x".to_string()), "x".parse::<Encoding>());
+    }
+}
@@ -1,3 +1,8 @@
 pub mod lambda;
 pub mod ski;
 pub mod iota;
+pub mod debruijn;
+pub mod turner;
+pub mod systemf;
+pub mod literal;
+pub mod combinator;
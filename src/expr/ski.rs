@@ -1,7 +1,275 @@
-#[derive(Debug, PartialEq, Eq, Clone)]
+use expr::combinator::Combinator;
+use expr::lambda::ParseError;
+
+use std::mem;
+
+/// SKI combinator term. Dropping, cloning and comparing use an explicit
+/// stack rather than recursion, so that deep terms do not overflow the stack.
+#[derive(Debug)]
 pub enum SKIExpr {
     Apply(Box<SKIExpr>, Box<SKIExpr>),
     S,
     K,
     I,
 }
+
+impl SKIExpr {
+    /// Application of `e1` to `e2`.
+    pub fn apply(e1: SKIExpr, e2: SKIExpr) -> SKIExpr {
+        SKIExpr::Apply(Box::new(e1), Box::new(e2))
+    }
+
+    /// Moves the term out, leaving `I` in its place.
+    pub fn take(&mut self) -> SKIExpr {
+        mem::replace(self, SKIExpr::I)
+    }
+
+    /// Function and argument of an application, or `None` for a combinator.
+    pub fn unapply(mut self) -> Option<(SKIExpr, SKIExpr)> {
+        match self {
+            SKIExpr::Apply(ref mut e1, ref mut e2) => Some((e1.take(), e2.take())),
+            _ => None,
+        }
+    }
+
+    /// Number of combinators and applications in the term.
+    pub fn size(&self) -> usize {
+        self.count()
+    }
+}
+
+impl Combinator for SKIExpr {
+    fn parts(&self) -> Option<(&SKIExpr, &SKIExpr)> {
+        match self {
+            SKIExpr::Apply(e1, e2) => Some((e1, e2)),
+            _ => None,
+        }
+    }
+
+    fn take_parts(&mut self) -> Option<(SKIExpr, SKIExpr)> {
+        match self {
+            SKIExpr::Apply(e1, e2) => Some((e1.take(), e2.take())),
+            _ => None,
+        }
+    }
+
+    fn join(e1: SKIExpr, e2: SKIExpr) -> SKIExpr {
+        SKIExpr::apply(e1, e2)
+    }
+
+    fn copy_leaf(&self) -> SKIExpr {
+        match self {
+            SKIExpr::Apply(..) => unreachable!(),
+            SKIExpr::S => SKIExpr::S,
+            SKIExpr::K => SKIExpr::K,
+            SKIExpr::I => SKIExpr::I,
+        }
+    }
+
+    fn same_leaf(&self, other: &SKIExpr) -> bool {
+        matches!((self, other), (SKIExpr::S, SKIExpr::S) | (SKIExpr::K, SKIExpr::K) | (SKIExpr::I, SKIExpr::I))
+    }
+}
+
+impl Clone for SKIExpr {
+    fn clone(&self) -> SKIExpr {
+        self.clone_tree()
+    }
+}
+
+impl PartialEq for SKIExpr {
+    fn eq(&self, other: &SKIExpr) -> bool {
+        self.eq_tree(other)
+    }
+}
+
+impl Eq for SKIExpr {}
+
+impl Drop for SKIExpr {
+    fn drop(&mut self) {
+        self.drop_tree();
+    }
+}
+
+#[derive(Debug)]
+struct Lexer<'a> {
+    source: &'a str,
+    contents: &'a str,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+#[allow(clippy::upper_case_acronyms)]
+enum TokenType {
+    Backtick,
+    S,
+    K,
+    I,
+    LParen,
+    RParen,
+    Unknown,
+    EOF,
+}
+
+impl TokenType {
+    fn can_begin_expr(&self) -> bool {
+        match *self {
+            TokenType::Backtick => true,
+            TokenType::S | TokenType::K | TokenType::I => true,
+            TokenType::LParen => true,
+            TokenType::RParen => false,
+            TokenType::Unknown => false,
+            TokenType::EOF => false,
+        }
+    }
+
+    fn describe(&self) -> &'static str {
+        match *self {
+            TokenType::Backtick => "'`'",
+            TokenType::S => "'S'",
+            TokenType::K => "'K'",
+            TokenType::I => "'I'",
+            TokenType::LParen => "'('",
+            TokenType::RParen => "')'",
+            TokenType::Unknown => "unknown character",
+            TokenType::EOF => "end of input",
+        }
+    }
+}
+
+impl<'a> Lexer<'a> {
+    fn new(contents: &str) -> Lexer<'_> {
+        Lexer { source: contents, contents: contents.trim_start() }
+    }
+
+    fn peek(&self) -> TokenType {
+        match self.contents.chars().next() {
+            Some('`') => TokenType::Backtick,
+            Some('S') | Some('s') => TokenType::S,
+            Some('K') | Some('k') => TokenType::K,
+            Some('I') | Some('i') => TokenType::I,
+            Some('(') => TokenType::LParen,
+            Some(')') => TokenType::RParen,
+            Some(_) => TokenType::Unknown,
+            None => TokenType::EOF,
+        }
+    }
+
+    fn consume(&mut self) {
+        // find index of next code point
+        let index = self.contents.char_indices().nth(1).map(|x| x.0).unwrap_or(self.contents.len());
+        self.contents = self.contents[index..].trim_start();
+    }
+
+    fn offset(&self) -> usize {
+        self.source.len() - self.contents.len()
+    }
+
+    /// Error at the next token, which was not `expected`.
+    fn error(&self, expected: &str) -> ParseError {
+        let (offset, found) = match self.peek() {
+            TokenType::EOF => (self.source.trim_end().len(), TokenType::EOF.describe().to_string()),
+            TokenType::Unknown => (self.offset(), format!("character '{}'", self.contents.chars().next().unwrap())),
+            t => (self.offset(), t.describe().to_string()),
+        };
+        ParseError::new(self.source, offset, expected, found)
+    }
+}
+
+fn parse_atom(lex: &mut Lexer) -> Result<SKIExpr, ParseError> {
+    match lex.peek() {
+        TokenType::S => { lex.consume(); Ok(SKIExpr::S) },
+        TokenType::K => { lex.consume(); Ok(SKIExpr::K) },
+        TokenType::I => { lex.consume(); Ok(SKIExpr::I) },
+        TokenType::Backtick => {
+            lex.consume();
+            let e1 = parse_atom(lex)?;
+            let e2 = parse_atom(lex)?;
+            Ok(SKIExpr::apply(e1, e2))
+        },
+        TokenType::LParen => {
+            lex.consume();
+            let result = parse_expr(lex)?;
+            if lex.peek() != TokenType::RParen {
+                Err(lex.error("')'"))
+            } else {
+                lex.consume();
+                Ok(result)
+            }
+        },
+        _ => Err(lex.error("expression")),
+    }
+}
+
+fn parse_expr(lex: &mut Lexer) -> Result<SKIExpr, ParseError> {
+    let mut tree = parse_atom(lex)?;
+    while lex.peek().can_begin_expr() {
+        tree = SKIExpr::apply(tree, parse_atom(lex)?);
+    }
+    Ok(tree)
+}
+
+/// Parses either the applicative form produced by `Display` (`S (K (S I)) K`)
+/// or the Unlambda form with prefix backticks (`` ``s`k`sik ``).
+/// The two may be mixed, and combinators may be upper or lower case.
+pub fn parse(s: &str) -> Result<SKIExpr, ParseError> {
+    let mut lex: Lexer = Lexer::new(s);
+    let result = parse_expr(&mut lex)?;
+    if lex.peek() != TokenType::EOF {
+        return Err(lex.error("end of input"));
+    }
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use expr::ski::*;
+    use expr::ski::SKIExpr::*;
+
+    fn flip() -> SKIExpr {
+        SKIExpr::apply(SKIExpr::apply(S, SKIExpr::apply(K, SKIExpr::apply(S, I))), K)
+    }
+
+    #[test]
+    fn single_combinator() {
+        assert_eq!(Ok(S), parse("S"));
+        assert_eq!(Ok(K), parse(" k "));
+        assert_eq!(Ok(I), parse("(I)"));
+    }
+
+    #[test]
+    fn applicative_form() {
+        assert_eq!(Ok(flip()), parse("S (K (S I)) K"));
+        assert_eq!(Ok(flip()), parse("s(k(si))k"));
+    }
+
+    #[test]
+    fn unlambda_form() {
+        assert_eq!(Ok(SKIExpr::apply(S, K)), parse("`sk"));
+        assert_eq!(Ok(flip()), parse("``s`k`sik"));
+    }
+
+    #[test]
+    fn display_round_trip() {
+        assert_eq!(Ok(flip()), parse(&format!("{}", flip())));
+    }
+
+    fn error(s: &str) -> String {
+        parse(s).unwrap_err().to_string()
+    }
+
+    #[test]
+    fn errors() {
+        assert_eq!("1:3: expected expression, found end of input", error("`s"));
+        assert_eq!("1:5: expected ')', found end of input", error("(S K"));
+        assert_eq!("1:4: expected end of input, found ')'", error("S K)"));
+        assert_eq!("1:3: expected end of input, found character 'x'", error("S x"));
+        assert_eq!("1:4: expected expression, found character 'x'", error("`S x"));
+        assert_eq!("2:4: expected ')', found end of input", error("(S\n  K \n"));
+    }
+
+    #[test]
+    fn error_render() {
+        let source = "S (K\n  x)";
+        assert_eq!("2:3: expected ')', found character 'x'\n  x)\n  ^", parse(source).unwrap_err().render(source));
+    }
+}
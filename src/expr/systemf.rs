@@ -0,0 +1,537 @@
+use expr::lambda::{bind_definitions, comment_len, Definition, LambdaExpr, ParseError};
+
+use std::collections::HashMap;
+use std::fmt;
+
+/// Type of System F: a type variable, a function type or a universal type.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum FType {
+    Var(String),
+    Arrow(Box<FType>, Box<FType>),
+    Forall(String, Box<FType>),
+}
+
+/// Term of System F. Erasing the types gives a `LambdaExpr`.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum FExpr {
+    Variable(String),
+    Apply(Box<FExpr>, Box<FExpr>),
+    /// `λx:T. e`
+    Lambda(String, FType, Box<FExpr>),
+    /// `Λa. e`
+    TypeLambda(String, Box<FExpr>),
+    /// `e [T]`
+    TypeApply(Box<FExpr>, FType),
+}
+
+fn write_type(f: &mut fmt::Formatter, ty: &FType, tail: bool) -> Result<(), fmt::Error> {
+    match ty {
+        FType::Var(v) => write!(f, "{}", v),
+        FType::Arrow(t1, t2) => {
+            if !tail { write!(f, "(")?; }
+            write_type(f, t1, false)?;
+            write!(f, " -> ")?;
+            write_type(f, t2, true)?;
+            if !tail { write!(f, ")")?; }
+            Ok(())
+        },
+        FType::Forall(v, t) => {
+            if !tail { write!(f, "(")?; }
+            write!(f, "∀{}. ", v)?;
+            write_type(f, t, true)?;
+            if !tail { write!(f, ")")?; }
+            Ok(())
+        },
+    }
+}
+
+impl fmt::Display for FType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        write_type(f, self, true)
+    }
+}
+
+/// Writes `expr`; `tail` is as for `LambdaExpr`, and `arg` is set when
+/// `expr` is an argument, so applications need parentheses.
+fn write_expr(f: &mut fmt::Formatter, expr: &FExpr, tail: bool, arg: bool) -> Result<(), fmt::Error> {
+    match expr {
+        FExpr::Variable(v) => write!(f, "{}", v),
+        FExpr::Apply(e1, e2) => {
+            if arg { write!(f, "(")?; }
+            write_expr(f, e1, false, false)?;
+            write!(f, " ")?;
+            write_expr(f, e2, tail || arg, true)?;
+            if arg { write!(f, ")")?; }
+            Ok(())
+        },
+        FExpr::TypeApply(e, t) => {
+            if arg { write!(f, "(")?; }
+            write_expr(f, e, false, false)?;
+            write!(f, " [{}]", t)?;
+            if arg { write!(f, ")")?; }
+            Ok(())
+        },
+        FExpr::Lambda(v, t, e) => {
+            if !tail { write!(f, "(")?; }
+            write!(f, "λ{}:{}. ", v, t)?;
+            write_expr(f, e, true, false)?;
+            if !tail { write!(f, ")")?; }
+            Ok(())
+        },
+        FExpr::TypeLambda(v, e) => {
+            if !tail { write!(f, "(")?; }
+            write!(f, "Λ{}. ", v)?;
+            write_expr(f, e, true, false)?;
+            if !tail { write!(f, ")")?; }
+            Ok(())
+        },
+    }
+}
+
+impl fmt::Display for FExpr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        write_expr(f, self, true, false)
+    }
+}
+
+/// Top-level definition `name : T = value`; the type is optional.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct FDefinition {
+    pub name: String,
+    pub ty: Option<FType>,
+    pub value: FExpr,
+}
+
+/// Definitions, optionally followed by a term. Type abbreviations
+/// (`type Nat = ∀a. (a -> a) -> a -> a`) are expanded while parsing.
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct FProgram {
+    pub definitions: Vec<FDefinition>,
+    pub body: Option<FExpr>,
+}
+
+impl FProgram {
+    /// The erased body with the erased definitions it uses bound,
+    /// or `None` if there is no body.
+    pub fn erase(&self) -> Option<LambdaExpr> {
+        let definitions: Vec<Definition> = self.definitions.iter().map(|def| Definition {
+            name: def.name.clone(),
+            value: LambdaExpr::from(def.value.clone()),
+        }).collect();
+        self.body.clone().map(|body| bind_definitions(&definitions, LambdaExpr::from(body)))
+    }
+}
+
+#[derive(Debug)]
+struct Lexer<'a> {
+    source: &'a str,
+    contents: &'a str,
+    /// Whether a token at the start of a line ends the term, as in `expr::lambda`.
+    layout: bool,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[allow(clippy::upper_case_acronyms)]
+enum TokenType {
+    Ident,
+    LParen,
+    RParen,
+    Lambda,
+    TypeLambda,
+    Forall,
+    Dot,
+    Colon,
+    Arrow,
+    LBracket,
+    RBracket,
+    Equals,
+    Type,
+    UnterminatedComment,
+    EOF,
+}
+
+impl TokenType {
+    fn can_begin_expr(&self) -> bool {
+        matches!(*self, TokenType::Ident | TokenType::LParen | TokenType::Lambda | TokenType::TypeLambda)
+    }
+
+    fn describe(&self) -> &'static str {
+        match *self {
+            TokenType::Ident => "identifier",
+            TokenType::LParen => "'('",
+            TokenType::RParen => "')'",
+            TokenType::Lambda => "'λ'",
+            TokenType::TypeLambda => "'Λ'",
+            TokenType::Forall => "'∀'",
+            TokenType::Dot => "'.'",
+            TokenType::Colon => "':'",
+            TokenType::Arrow => "'->'",
+            TokenType::LBracket => "'['",
+            TokenType::RBracket => "']'",
+            TokenType::Equals => "'='",
+            TokenType::Type => "'type'",
+            TokenType::UnterminatedComment => "unterminated comment",
+            TokenType::EOF => "end of input",
+        }
+    }
+}
+
+/// Punctuation and its token, longest first.
+const SYMBOLS: &[(&str, TokenType)] = &[
+    ("->", TokenType::Arrow), ("/\\", TokenType::TypeLambda),
+    ("(", TokenType::LParen), (")", TokenType::RParen),
+    ("\\", TokenType::Lambda), ("λ", TokenType::Lambda), ("Λ", TokenType::TypeLambda),
+    ("∀", TokenType::Forall), ("→", TokenType::Arrow),
+    (".", TokenType::Dot), (":", TokenType::Colon),
+    ("[", TokenType::LBracket), ("]", TokenType::RBracket), ("=", TokenType::Equals),
+];
+
+impl<'a> Lexer<'a> {
+    fn new(contents: &str) -> Lexer<'_> {
+        let mut lex = Lexer { source: contents, contents, layout: false };
+        lex.skip_whitespace();
+        lex
+    }
+
+    /// Skips whitespace and comments, as `expr::lambda` does. Stops at the
+    /// start of an unterminated block comment.
+    fn skip_whitespace(&mut self) {
+        self.contents = self.contents.trim_start();
+        while let Some(len) = comment_len(self.contents) {
+            self.contents = self.contents[len..].trim_start();
+        }
+    }
+
+    fn offset(&self) -> usize {
+        self.source.len() - self.contents.len()
+    }
+
+    fn at_line_start(&self) -> bool {
+        let before = &self.source[..self.offset()];
+        before.is_empty() || before.ends_with('\n')
+    }
+
+    /// Error at the next token, which was not `expected`.
+    fn error(&self, expected: &str) -> ParseError {
+        let (offset, found) = match self.peek() {
+            TokenType::EOF => (self.source.trim_end().len(), TokenType::EOF.describe().to_string()),
+            TokenType::Ident => (self.offset(), format!("identifier '{}'", &self.contents[..self.ident_len()])),
+            t => (self.offset(), t.describe().to_string()),
+        };
+        ParseError::new(self.source, offset, expected, found)
+    }
+
+    /// Whether the next tokens are `name =` or `name :`, beginning a definition.
+    fn at_definition(&self) -> bool {
+        let mut copy = Lexer { source: self.source, contents: self.contents, layout: false };
+        if copy.peek() != TokenType::Ident {
+            return false;
+        }
+        copy.consume();
+        matches!(copy.peek(), TokenType::Equals | TokenType::Colon)
+    }
+
+    fn ident_len(&self) -> usize {
+        let contents = self.contents;
+        contents.char_indices().find(|&(i, c)| {
+            c.is_whitespace() || contents[i..].starts_with("--") || contents[i..].starts_with("{-")
+                || SYMBOLS.iter().any(|&(s, _)| contents[i..].starts_with(s))
+        }).map(|x| x.0).unwrap_or(contents.len())
+    }
+
+    fn peek(&self) -> TokenType {
+        if self.contents.is_empty() {
+            return TokenType::EOF;
+        }
+        if self.contents.starts_with("{-") {
+            // otherwise skipped
+            return TokenType::UnterminatedComment;
+        }
+        if let Some(&(_, t)) = SYMBOLS.iter().find(|&&(s, _)| self.contents.starts_with(s)) {
+            return t;
+        }
+        match &self.contents[..self.ident_len()] {
+            "forall" => TokenType::Forall,
+            "type" => TokenType::Type,
+            _ => TokenType::Ident,
+        }
+    }
+
+    fn consume(&mut self) -> Option<&'a str> {
+        let len = match self.peek() {
+            TokenType::EOF | TokenType::UnterminatedComment => return None,
+            TokenType::Ident | TokenType::Type => self.ident_len(),
+            TokenType::Forall if self.contents.starts_with("forall") => "forall".len(),
+            _ => SYMBOLS.iter().find(|&&(s, _)| self.contents.starts_with(s)).unwrap().0.len(),
+        };
+        let (ret, rest) = self.contents.split_at(len);
+        self.contents = rest;
+        self.skip_whitespace();
+        Some(ret)
+    }
+
+    fn expect(&mut self, token: TokenType) -> Result<(), ParseError> {
+        if self.peek() != token {
+            return Err(self.error(token.describe()));
+        }
+        self.consume();
+        Ok(())
+    }
+
+    fn ident(&mut self, expected: &str) -> Result<String, ParseError> {
+        if self.peek() != TokenType::Ident {
+            return Err(self.error(expected));
+        }
+        Ok(self.consume().unwrap().to_string())
+    }
+}
+
+struct Parser<'a> {
+    lex: Lexer<'a>,
+    aliases: HashMap<String, FType>,
+    /// Type variables bound by enclosing `∀`s, which shadow aliases.
+    bound: Vec<String>,
+}
+
+impl<'a> Parser<'a> {
+    fn parse_type_atom(&mut self) -> Result<FType, ParseError> {
+        match self.lex.peek() {
+            TokenType::Ident => {
+                let name = self.lex.consume().unwrap().to_string();
+                match self.aliases.get(&name) {
+                    Some(t) if !self.bound.contains(&name) => Ok(t.clone()),
+                    _ => Ok(FType::Var(name)),
+                }
+            },
+            TokenType::LParen => {
+                self.lex.consume();
+                let t = self.parse_type()?;
+                self.lex.expect(TokenType::RParen)?;
+                Ok(t)
+            },
+            TokenType::Forall => {
+                // ∀a b. T is ∀a. ∀b. T
+                self.lex.consume();
+                let mut vars = vec![self.lex.ident("type variable after '∀'")?];
+                while self.lex.peek() == TokenType::Ident {
+                    vars.push(self.lex.consume().unwrap().to_string());
+                }
+                self.lex.expect(TokenType::Dot)?;
+                let depth = self.bound.len();
+                self.bound.extend(vars.iter().cloned());
+                let body = self.parse_type();
+                self.bound.truncate(depth);
+                Ok(vars.into_iter().rev().fold(body?, |t, v| FType::Forall(v, Box::new(t))))
+            },
+            _ => Err(self.lex.error("type")),
+        }
+    }
+
+    fn parse_type(&mut self) -> Result<FType, ParseError> {
+        let t = self.parse_type_atom()?;
+        if self.lex.peek() == TokenType::Arrow {
+            self.lex.consume();
+            let result = self.parse_type()?;
+            return Ok(FType::Arrow(Box::new(t), Box::new(result)));
+        }
+        Ok(t)
+    }
+
+    fn parse_atom(&mut self) -> Result<FExpr, ParseError> {
+        match self.lex.peek() {
+            TokenType::Ident => Ok(FExpr::Variable(self.lex.consume().unwrap().to_string())),
+            TokenType::LParen => {
+                self.lex.consume();
+                let layout = self.lex.layout;
+                self.lex.layout = false;
+                let result = self.parse_expr()?;
+                self.lex.layout = layout;
+                self.lex.expect(TokenType::RParen)?;
+                Ok(result)
+            },
+            TokenType::Lambda => {
+                self.lex.consume();
+                let name = self.lex.ident("parameter name after 'λ'")?;
+                self.lex.expect(TokenType::Colon)?;
+                let t = self.parse_type()?;
+                self.lex.expect(TokenType::Dot)?;
+                let body = self.parse_expr()?;
+                Ok(FExpr::Lambda(name, t, Box::new(body)))
+            },
+            TokenType::TypeLambda => {
+                // Λa b. e is Λa. Λb. e
+                self.lex.consume();
+                let mut vars = vec![self.lex.ident("type variable after 'Λ'")?];
+                while self.lex.peek() == TokenType::Ident {
+                    vars.push(self.lex.consume().unwrap().to_string());
+                }
+                self.lex.expect(TokenType::Dot)?;
+                let depth = self.bound.len();
+                self.bound.extend(vars.iter().cloned());
+                let body = self.parse_expr();
+                self.bound.truncate(depth);
+                Ok(vars.into_iter().rev().fold(body?, |e, v| FExpr::TypeLambda(v, Box::new(e))))
+            },
+            _ => Err(self.lex.error("expression")),
+        }
+    }
+
+    fn parse_expr(&mut self) -> Result<FExpr, ParseError> {
+        let mut tree = self.parse_atom()?;
+        loop {
+            let next = self.lex.peek();
+            if self.lex.layout && self.lex.at_line_start() {
+                break;
+            } else if next == TokenType::LBracket {
+                self.lex.consume();
+                let t = self.parse_type()?;
+                self.lex.expect(TokenType::RBracket)?;
+                tree = FExpr::TypeApply(Box::new(tree), t);
+            } else if next.can_begin_expr() {
+                tree = FExpr::Apply(Box::new(tree), Box::new(self.parse_atom()?));
+            } else {
+                break;
+            }
+        }
+        Ok(tree)
+    }
+
+    fn parse_program(&mut self) -> Result<FProgram, ParseError> {
+        let mut program = FProgram::default();
+        loop {
+            if self.lex.peek() == TokenType::Type {
+                self.lex.consume();
+                let name = self.lex.ident("type name after 'type'")?;
+                self.lex.expect(TokenType::Equals)?;
+                let t = self.parse_type()?;
+                self.aliases.insert(name, t);
+            } else if self.lex.at_definition() {
+                let name = self.lex.consume().unwrap().to_string();
+                let ty = if self.lex.peek() == TokenType::Colon {
+                    self.lex.consume();
+                    Some(self.parse_type()?)
+                } else {
+                    None
+                };
+                self.lex.expect(TokenType::Equals)?;
+                self.lex.layout = true;
+                let value = self.parse_expr()?;
+                self.lex.layout = false;
+                program.definitions.push(FDefinition { name, ty, value });
+            } else {
+                break;
+            }
+        }
+        if self.lex.peek() != TokenType::EOF {
+            program.body = Some(self.parse_expr()?);
+            if self.lex.peek() != TokenType::EOF {
+                return Err(self.lex.error(TokenType::EOF.describe()));
+            }
+        }
+        Ok(program)
+    }
+}
+
+fn parser(s: &str) -> Parser<'_> {
+    Parser { lex: Lexer::new(s), aliases: HashMap::new(), bound: Vec::new() }
+}
+
+/// Parses a term. Binders are annotated, `λx:T. e`; type abstraction is
+/// `Λa. e` or `/\a. e` and type application `e [T]`. Types are written
+/// `a`, `T -> U` and `∀a. T` or `forall a. T`.
+pub fn parse(s: &str) -> Result<FExpr, ParseError> {
+    let mut p = parser(s);
+    let result = p.parse_expr()?;
+    if p.lex.peek() != TokenType::EOF {
+        return Err(p.lex.error(TokenType::EOF.describe()));
+    }
+    Ok(result)
+}
+
+pub fn parse_type(s: &str) -> Result<FType, ParseError> {
+    let mut p = parser(s);
+    let result = p.parse_type()?;
+    if p.lex.peek() != TokenType::EOF {
+        return Err(p.lex.error(TokenType::EOF.describe()));
+    }
+    Ok(result)
+}
+
+/// Parses type abbreviations `type Name = T` and definitions `name : T = value`
+/// (with the type optional), optionally followed by a term. As in `expr::lambda`,
+/// a definition continues until a line that starts without indentation.
+pub fn parse_program(s: &str) -> Result<FProgram, ParseError> {
+    parser(s).parse_program()
+}
+
+#[cfg(test)]
+mod tests {
+    use expr::systemf::*;
+
+    fn var(v: &str) -> FType {
+        FType::Var(v.to_string())
+    }
+
+    fn arrow(t1: FType, t2: FType) -> FType {
+        FType::Arrow(Box::new(t1), Box::new(t2))
+    }
+
+    #[test]
+    fn types() {
+        assert_eq!(Ok(arrow(var("a"), arrow(var("b"), var("a")))), parse_type("a -> b -> a"));
+        assert_eq!(Ok(arrow(arrow(var("a"), var("b")), var("a"))), parse_type("(a → b) -> a"));
+        let id = FType::Forall("a".to_string(), Box::new(arrow(var("a"), var("a"))));
+        assert_eq!(Ok(id.clone()), parse_type("∀a. a -> a"));
+        assert_eq!(Ok(id), parse_type("forall a. a -> a"));
+    }
+
+    #[test]
+    fn terms() {
+        let e = parse("Λa. λx:a. x").unwrap();
+        assert_eq!(FExpr::TypeLambda("a".to_string(), Box::new(FExpr::Lambda("x".to_string(), var("a"),
+            Box::new(FExpr::Variable("x".to_string()))))), e);
+        assert_eq!(e, parse("/\\a. \\x:a. x").unwrap());
+        let e = parse("f [a -> a] x").unwrap();
+        assert_eq!(FExpr::Apply(Box::new(FExpr::TypeApply(Box::new(FExpr::Variable("f".to_string())),
+            arrow(var("a"), var("a")))), Box::new(FExpr::Variable("x".to_string()))), e);
+    }
+
+    #[test]
+    fn display() {
+        for s in &["Λa. λf:a -> a. λx:a. f (f x)", "λx:∀a. a -> a. x [∀a. a -> a] x", "f (g [a]) (λx:a. x)"] {
+            let e = parse(s).unwrap();
+            assert_eq!(Ok(e.clone()), parse(&e.to_string()));
+        }
+        assert_eq!("(∀a. a) -> ∀b. b", parse_type("(∀a. a) -> ∀b. b").unwrap().to_string());
+    }
+
+    #[test]
+    fn aliases() {
+        let program = parse_program("type Id = ∀a. a -> a\nid : Id = Λa. λx:a. x\nf = λg:Id. g [Id] g\n  -- comment\nf id").unwrap();
+        assert_eq!(2, program.definitions.len());
+        assert_eq!(parse_type("∀a. a -> a").ok(), program.definitions[0].ty);
+        assert_eq!(parse("λg:∀a. a -> a. g [∀a. a -> a] g").unwrap(), program.definitions[1].value);
+        assert_eq!(parse("f id").ok(), program.body);
+        // bound type variables shadow abbreviations
+        let program = parse_program("type a = b -> b\nx = Λa. λy:a. y").unwrap();
+        assert_eq!(parse("Λa. λy:a. y").unwrap(), program.definitions[0].value);
+    }
+
+    #[test]
+    fn errors() {
+        assert_eq!("':'", parse("λx. x").unwrap_err().expected);
+        assert_eq!("type", parse("λx:. x").unwrap_err().expected);
+        assert_eq!("']'", parse("f [a").unwrap_err().expected);
+        let err = parse_program("x : a\n  = y ]").unwrap_err();
+        assert_eq!((2, "']'".to_string()), (err.line, err.found));
+        assert_eq!("unterminated comment", parse("λx:a. x {- {- -}").unwrap_err().found);
+    }
+
+    #[test]
+    fn comments() {
+        let program = parse_program("{- identity,\n  {- nested -} -}\nid = Λa. λx:a.{-the argument-}x\nid -- applied").unwrap();
+        assert_eq!(parse("Λa. λx:a. x").unwrap(), program.definitions[0].value);
+        assert_eq!(parse("id").ok(), program.body);
+        assert_eq!(parse("f g").unwrap(), parse("f{- -}g").unwrap());
+    }
+}
@@ -0,0 +1,112 @@
+use expr::combinator::Combinator;
+
+use std::mem;
+
+/// Combinator term using Turner's extended set, where
+/// `B f g x = f (g x)`, `C f g x = f x g`, `S' c f g x = c (f x) (g x)`,
+/// `B* c f g x = c (f (g x))` and `C' c f g x = c (f x) g`.
+///
+/// The bulk combinators take `n` extra arguments:
+/// `Bn f g x1..xn = f (g x1..xn)`, `Cn f g x1..xn = f x1..xn g` and
+/// `Sn f g x1..xn = f x1..xn (g x1..xn)`.
+///
+/// Dropping, cloning and comparing use an explicit stack rather than
+/// recursion, so that deep terms do not overflow the stack.
+#[derive(Debug)]
+pub enum TurnerExpr {
+    Apply(Box<TurnerExpr>, Box<TurnerExpr>),
+    S,
+    K,
+    I,
+    B,
+    C,
+    SPrime,
+    BStar,
+    CPrime,
+    BulkB(usize),
+    BulkC(usize),
+    BulkS(usize),
+}
+
+impl TurnerExpr {
+    /// Application of `e1` to `e2`.
+    pub fn apply(e1: TurnerExpr, e2: TurnerExpr) -> TurnerExpr {
+        TurnerExpr::Apply(Box::new(e1), Box::new(e2))
+    }
+
+    /// Moves the term out, leaving `I` in its place.
+    pub fn take(&mut self) -> TurnerExpr {
+        mem::replace(self, TurnerExpr::I)
+    }
+
+    /// Number of combinators and applications in the term.
+    pub fn size(&self) -> usize {
+        self.count()
+    }
+}
+
+impl Combinator for TurnerExpr {
+    fn parts(&self) -> Option<(&TurnerExpr, &TurnerExpr)> {
+        match self {
+            TurnerExpr::Apply(e1, e2) => Some((e1, e2)),
+            _ => None,
+        }
+    }
+
+    fn take_parts(&mut self) -> Option<(TurnerExpr, TurnerExpr)> {
+        match self {
+            TurnerExpr::Apply(e1, e2) => Some((e1.take(), e2.take())),
+            _ => None,
+        }
+    }
+
+    fn join(e1: TurnerExpr, e2: TurnerExpr) -> TurnerExpr {
+        TurnerExpr::apply(e1, e2)
+    }
+
+    fn copy_leaf(&self) -> TurnerExpr {
+        match *self {
+            TurnerExpr::Apply(..) => unreachable!(),
+            TurnerExpr::S => TurnerExpr::S,
+            TurnerExpr::K => TurnerExpr::K,
+            TurnerExpr::I => TurnerExpr::I,
+            TurnerExpr::B => TurnerExpr::B,
+            TurnerExpr::C => TurnerExpr::C,
+            TurnerExpr::SPrime => TurnerExpr::SPrime,
+            TurnerExpr::BStar => TurnerExpr::BStar,
+            TurnerExpr::CPrime => TurnerExpr::CPrime,
+            TurnerExpr::BulkB(n) => TurnerExpr::BulkB(n),
+            TurnerExpr::BulkC(n) => TurnerExpr::BulkC(n),
+            TurnerExpr::BulkS(n) => TurnerExpr::BulkS(n),
+        }
+    }
+
+    fn same_leaf(&self, other: &TurnerExpr) -> bool {
+        match (self, other) {
+            (TurnerExpr::BulkB(m), TurnerExpr::BulkB(n))
+            | (TurnerExpr::BulkC(m), TurnerExpr::BulkC(n))
+            | (TurnerExpr::BulkS(m), TurnerExpr::BulkS(n)) => m == n,
+            _ => mem::discriminant(self) == mem::discriminant(other),
+        }
+    }
+}
+
+impl Clone for TurnerExpr {
+    fn clone(&self) -> TurnerExpr {
+        self.clone_tree()
+    }
+}
+
+impl PartialEq for TurnerExpr {
+    fn eq(&self, other: &TurnerExpr) -> bool {
+        self.eq_tree(other)
+    }
+}
+
+impl Eq for TurnerExpr {}
+
+impl Drop for TurnerExpr {
+    fn drop(&mut self) {
+        self.drop_tree();
+    }
+}
@@ -0,0 +1,249 @@
+use expr::debruijn::DbExpr;
+use expr::lambda::LambdaExpr;
+use env::{extend, lookup, Env, Level};
+
+use std::cell::RefCell;
+use std::mem;
+use std::rc::Rc;
+
+/// Shared node of the graph. Every use of an argument refers to the same
+/// node, which is overwritten with its value the first time it is needed,
+/// so no argument is evaluated more than once.
+type Thunk<'a> = Rc<RefCell<Node<'a>>>;
+
+enum Node<'a> {
+    /// Term not yet evaluated, with the arguments its variables refer to.
+    Suspended(&'a DbExpr, Env<Thunk<'a>>),
+    /// Under evaluation; needing it again means it depends on its own value.
+    Evaluating,
+    Value(Value<'a>),
+}
+
+/// Weak head normal form.
+#[derive(Clone)]
+enum Value<'a> {
+    /// Lambda with its name hint and body.
+    Closure(&'a str, &'a DbExpr, Env<Thunk<'a>>),
+    /// Variable applied to arguments.
+    Neutral(Head<'a>, Vec<Thunk<'a>>),
+}
+
+#[derive(Clone, Copy)]
+enum Head<'a> {
+    Free(&'a str),
+    Level(Level),
+}
+
+fn thunk(node: Node) -> Thunk {
+    Rc::new(RefCell::new(node))
+}
+
+enum Control<'a> {
+    Eval(&'a DbExpr, Env<Thunk<'a>>),
+    Return(Value<'a>),
+}
+
+enum Frame<'a> {
+    /// Argument for the function being evaluated.
+    Arg(Thunk<'a>),
+    /// Node to overwrite with the value being computed.
+    Update(Thunk<'a>),
+}
+
+struct Machine {
+    steps: u32,
+    limit: u32,
+}
+
+impl Machine {
+    /// Evaluates to weak head normal form, or `None` if that takes more
+    /// than the remaining steps or a node depends on its own value.
+    fn whnf<'a>(&mut self, mut control: Control<'a>, mut stack: Vec<Frame<'a>>) -> Option<Value<'a>> {
+        loop {
+            let value = match control {
+                Control::Eval(expr, env) => match expr {
+                    DbExpr::Free(v) => Value::Neutral(Head::Free(v), Vec::new()),
+                    DbExpr::Bound(i) => {
+                        let thunk = lookup(&env, *i);
+                        let node = mem::replace(&mut *thunk.borrow_mut(), Node::Evaluating);
+                        match node {
+                            Node::Suspended(e, env) => {
+                                stack.push(Frame::Update(thunk));
+                                control = Control::Eval(e, env);
+                                continue
+                            },
+                            Node::Evaluating => return None,
+                            Node::Value(value) => {
+                                *thunk.borrow_mut() = Node::Value(value.clone());
+                                value
+                            },
+                        }
+                    },
+                    DbExpr::Apply(e1, e2) => {
+                        // a variable argument shares the node it refers to
+                        let arg = match **e2 {
+                            DbExpr::Bound(i) => lookup(&env, i),
+                            ref e2 => thunk(Node::Suspended(e2, env.clone())),
+                        };
+                        stack.push(Frame::Arg(arg));
+                        control = Control::Eval(e1, env);
+                        continue
+                    },
+                    DbExpr::Lambda(v, e) => Value::Closure(v, e, env),
+                },
+                Control::Return(value) => value,
+            };
+            control = match stack.pop() {
+                None => return Some(value),
+                Some(Frame::Update(thunk)) => {
+                    *thunk.borrow_mut() = Node::Value(value.clone());
+                    Control::Return(value)
+                },
+                Some(Frame::Arg(arg)) => match value {
+                    Value::Closure(_, body, env) => {
+                        if self.steps == self.limit {
+                            return None;
+                        }
+                        self.steps += 1;
+                        Control::Eval(body, extend(&env, arg))
+                    },
+                    Value::Neutral(head, mut args) => {
+                        args.push(arg);
+                        Control::Return(Value::Neutral(head, args))
+                    },
+                },
+            };
+        }
+    }
+
+    fn force<'a>(&mut self, thunk: Thunk<'a>) -> Option<Value<'a>> {
+        let node = mem::replace(&mut *thunk.borrow_mut(), Node::Evaluating);
+        match node {
+            Node::Suspended(e, env) => self.whnf(Control::Eval(e, env), vec![Frame::Update(thunk)]),
+            Node::Evaluating => None,
+            Node::Value(value) => {
+                *thunk.borrow_mut() = Node::Value(value.clone());
+                Some(value)
+            },
+        }
+    }
+
+    /// Normal form of a value, evaluating under lambdas and in the
+    /// arguments of variables.
+    fn readback<'a>(&mut self, value: Value<'a>) -> Option<DbExpr> {
+        enum Task<'a> {
+            Read(Value<'a>, usize),
+            Force(Thunk<'a>, usize),
+            Apply,
+            Lambda(&'a str),
+        }
+        let mut tasks = vec![Task::Read(value, 0)];
+        let mut built = Vec::new();
+        while let Some(task) = tasks.pop() {
+            match task {
+                Task::Read(Value::Closure(hint, body, env), depth) => {
+                    let var = thunk(Node::Value(Value::Neutral(Head::Level(Level(depth)), Vec::new())));
+                    let value = self.whnf(Control::Eval(body, extend(&env, var)), Vec::new())?;
+                    tasks.push(Task::Lambda(hint));
+                    tasks.push(Task::Read(value, depth + 1));
+                },
+                Task::Read(Value::Neutral(head, args), depth) => {
+                    built.push(match head {
+                        Head::Free(v) => DbExpr::Free(v.to_string()),
+                        Head::Level(level) => level.at(depth),
+                    });
+                    for arg in args.into_iter().rev() {
+                        tasks.push(Task::Apply);
+                        tasks.push(Task::Force(arg, depth));
+                    }
+                },
+                Task::Force(thunk, depth) => {
+                    let value = self.force(thunk)?;
+                    tasks.push(Task::Read(value, depth));
+                },
+                Task::Apply => {
+                    let e2 = built.pop().unwrap();
+                    let e1 = built.pop().unwrap();
+                    built.push(DbExpr::Apply(Box::new(e1), Box::new(e2)));
+                },
+                Task::Lambda(hint) => {
+                    let e = built.pop().unwrap();
+                    built.push(DbExpr::Lambda(hint.to_string(), Box::new(e)));
+                },
+            }
+        }
+        built.pop()
+    }
+}
+
+/// Normal form of `expr` by call-by-need graph reduction, along with the
+/// number of beta-reductions performed. Unlike `LambdaExpr::replace`, which
+/// copies an argument into every occurrence, arguments are shared and
+/// evaluated at most once, and only if needed.
+/// Returns `None` if more than `limit` reductions are needed, or the
+/// evaluation of an argument needs its own value (and so never finishes).
+pub fn normalize(expr: &LambdaExpr, limit: u32) -> Option<(LambdaExpr, u32)> {
+    let expr = DbExpr::from(expr.clone());
+    let mut machine = Machine { steps: 0, limit };
+    let value = machine.whnf(Control::Eval(&expr, None), Vec::new())?;
+    let normal = machine.readback(value)?;
+    Some((LambdaExpr::from(normal), machine.steps))
+}
+
+#[cfg(test)]
+mod tests {
+    use graph::*;
+    use expr::lambda::parse;
+    use encoding::{numeral, Encoding};
+    use prelude;
+
+    #[test]
+    fn normalize_simple() {
+        assert_eq!(Some((parse("y").unwrap(), 1)), normalize(&parse("(\\x x) y").unwrap(), 10));
+        assert_eq!(Some((parse("\\f \\x f (f x)").unwrap(), 3)),
+            normalize(&parse("(\\n \\f \\x f (n f x)) (\\f \\x f x)").unwrap(), 10));
+        // under lambdas and in arguments of free variables
+        assert_eq!(Some((parse("\\y g (f y)").unwrap(), 2)), normalize(&parse("\\y g ((\\x f x) ((\\z z) y))").unwrap(), 10));
+    }
+
+    #[test]
+    fn normalize_no_capture() {
+        let (e, _) = normalize(&parse("(\\x \\y x) y").unwrap(), 10).unwrap();
+        assert!(e.alpha_eq(&parse("\\z y").unwrap()));
+        assert_eq!(parse("\\y1 y").unwrap(), e);
+    }
+
+    #[test]
+    fn normalize_lazy() {
+        // the unused argument is never evaluated
+        assert_eq!(Some((parse("y").unwrap(), 1)), normalize(&parse("(\\x y) ((\\x x x) (\\x x x))").unwrap(), 10));
+        assert_eq!(None, normalize(&parse("(\\x x x) (\\x x x)").unwrap(), 1000));
+        // the argument of a self-application needs its own value
+        let y = parse("\\f (\\x f (x x)) (\\x f (x x))").unwrap();
+        assert_eq!(None, normalize(&LambdaExpr::apply(y, parse("\\x x").unwrap()), 1000));
+    }
+
+    #[test]
+    fn normalize_shares_arguments() {
+        // the argument is reduced once, not once per use
+        let e = parse("(\\x \\f f x x x) ((\\y y) z)").unwrap();
+        assert_eq!(Some((parse("\\f f z z z").unwrap(), 2)), normalize(&e, 10));
+        assert_eq!(4, e.repeated_beta(10).1);
+    }
+
+    #[test]
+    fn normalize_factorial() {
+        let e = parse(include_str!("../factorial.lambda")).unwrap();
+        let e = LambdaExpr::apply(e, numeral(5, Encoding::Church));
+        // normal order tree reduction takes 27112 steps
+        let (e, steps) = normalize(&e, 100000).unwrap();
+        assert!(e.alpha_eq(&numeral(120, Encoding::Church)));
+        assert_eq!(9190, steps);
+    }
+
+    #[test]
+    fn normalize_prelude() {
+        let e = prelude::parse("mul (exp 2 3) (pred 4)").unwrap();
+        assert!(normalize(&e, 10000).unwrap().0.alpha_eq(&numeral(24, Encoding::Church)));
+    }
+}
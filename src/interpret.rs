@@ -1,65 +1,519 @@
-use expr::lambda::LambdaExpr;
+use expr::lambda::{LambdaExpr, Node};
+use expr::debruijn::DbExpr;
+use expr::ski::SKIExpr;
+use expr::iota::IotaExpr;
+
+use std::fmt;
+use std::mem;
+use std::rc::Rc;
+use std::str::FromStr;
+
+/// Order in which redexes are chosen during beta reduction.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Strategy {
+    /// Leftmost-outermost redex first; finds the normal form if one exists.
+    NormalOrder,
+    /// Leftmost-innermost redex first; arguments are normalized before substitution.
+    ApplicativeOrder,
+    /// Leftmost-outermost, but never under a lambda.
+    CallByName,
+    /// Leftmost-innermost, but never under a lambda.
+    CallByValue,
+    /// Only the head redex, including under lambdas; stops at head normal form.
+    Head,
+    /// Only the head redex, never under a lambda; stops at weak head normal form.
+    WeakHead,
+}
+
+impl Strategy {
+    pub fn all() -> &'static [Strategy] {
+        &[Strategy::NormalOrder, Strategy::ApplicativeOrder,
+          Strategy::CallByName, Strategy::CallByValue,
+          Strategy::Head, Strategy::WeakHead]
+    }
+
+    pub fn name(&self) -> &'static str {
+        match *self {
+            Strategy::NormalOrder => "normal",
+            Strategy::ApplicativeOrder => "applicative",
+            Strategy::CallByName => "cbn",
+            Strategy::CallByValue => "cbv",
+            Strategy::Head => "head",
+            Strategy::WeakHead => "whnf",
+        }
+    }
+
+    fn reduces_under_lambda(&self) -> bool {
+        match *self {
+            Strategy::NormalOrder | Strategy::ApplicativeOrder | Strategy::Head => true,
+            Strategy::CallByName | Strategy::CallByValue | Strategy::WeakHead => false,
+        }
+    }
+
+    fn reduces_arguments(&self) -> bool {
+        match *self {
+            Strategy::NormalOrder | Strategy::ApplicativeOrder
+                | Strategy::CallByName | Strategy::CallByValue => true,
+            Strategy::Head | Strategy::WeakHead => false,
+        }
+    }
+
+    fn is_eager(&self) -> bool {
+        match *self {
+            Strategy::ApplicativeOrder | Strategy::CallByValue => true,
+            Strategy::NormalOrder | Strategy::CallByName
+                | Strategy::Head | Strategy::WeakHead => false,
+        }
+    }
+}
+
+impl fmt::Display for Strategy {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        write!(f, "{}", self.name())
+    }
+}
+
+impl FromStr for Strategy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Strategy, String> {
+        Strategy::all().iter().cloned().find(|st| st.name() == s)
+            .ok_or_else(|| format!("unknown strategy: {}", s))
+    }
+}
+
+/// Argument of a substitution: the original argument, or a variable
+/// introduced by alpha-conversion.
+#[derive(Clone)]
+enum Arg<'a> {
+    Term(&'a LambdaExpr),
+    Var(Rc<str>),
+}
+
+impl<'a> Arg<'a> {
+    fn contains(&self, var: &str) -> bool {
+        match self {
+            Arg::Term(e) => e.contains(var),
+            Arg::Var(v) => &**v == var,
+        }
+    }
+
+    fn to_expr(&self) -> LambdaExpr {
+        match self {
+            Arg::Term(e) => (*e).clone(),
+            Arg::Var(v) => LambdaExpr::Variable(v.to_string()),
+        }
+    }
+}
+
+/// Position of a subterm relative to its parent.
+#[derive(Clone, Copy)]
+pub(crate) enum Step {
+    Function,
+    Argument,
+    Body,
+}
 
 impl LambdaExpr {
     pub fn contains(&self, var: &str) -> bool {
-        match self {
-            &LambdaExpr::Variable(ref v) => v == var,
-            &LambdaExpr::Apply(ref e1, ref e2) => e1.contains(var) || e2.contains(var),
-            &LambdaExpr::Lambda(ref v, ref e) => v != var && e.contains(var),
+        let mut stack = vec![self];
+        while let Some(e) = stack.pop() {
+            match e {
+                LambdaExpr::Variable(v) => if v == var { return true; },
+                LambdaExpr::Apply(e1, e2) => {
+                    stack.push(e2);
+                    stack.push(e1);
+                },
+                LambdaExpr::Lambda(v, e) => if v != var { stack.push(e); },
+            }
         }
+        false
     }
 
     pub fn replace(self, param: &str, arg: &LambdaExpr) -> LambdaExpr {
-        match self {
-            LambdaExpr::Variable(v) => if v == param { arg.clone() } else { LambdaExpr::Variable(v) },
-            LambdaExpr::Apply(e1, e2) => LambdaExpr::Apply(Box::new(e1.replace(param, arg)), Box::new(e2.replace(param, arg))),
-            LambdaExpr::Lambda(v, e) => if v == param { LambdaExpr::Lambda(v, e) } else {
-                if arg.contains(&v) {
-                    // alpha-conversion
-                    let new_name = {
-                        let mut name = v.clone();
-                        name.push('\'');
-                        name
-                    };
-                    LambdaExpr::Lambda(new_name.clone(), Box::new(e.replace(&v, &LambdaExpr::Variable(new_name)).replace(param, arg)))
-                } else {
-                    LambdaExpr::Lambda(v, Box::new(e.replace(param, arg)))
-                }
+        enum Task<'a> {
+            Replace(LambdaExpr, Rc<str>, Arg<'a>),
+            /// Replaces in the last term built.
+            ReplaceBuilt(Rc<str>, Arg<'a>),
+            Apply,
+            Lambda(String),
+        }
+        let mut tasks = vec![Task::Replace(self, Rc::from(param), Arg::Term(arg))];
+        let mut built = Vec::new();
+        while let Some(task) = tasks.pop() {
+            match task {
+                Task::Replace(e, param, arg) => match e.into_node() {
+                    Node::Variable(v) => built.push(if *v == *param { arg.to_expr() } else { LambdaExpr::Variable(v) }),
+                    Node::Apply(e1, e2) => {
+                        tasks.push(Task::Apply);
+                        tasks.push(Task::Replace(e2, param.clone(), arg.clone()));
+                        tasks.push(Task::Replace(e1, param, arg));
+                    },
+                    Node::Lambda(v, e) => if *v == *param {
+                        built.push(LambdaExpr::Lambda(v, Box::new(e)));
+                    } else if arg.contains(&v) {
+                        // alpha-conversion
+                        let new_name = {
+                            let mut name = v.clone();
+                            name.push('\'');
+                            name
+                        };
+                        tasks.push(Task::Lambda(new_name.clone()));
+                        tasks.push(Task::ReplaceBuilt(param, arg));
+                        tasks.push(Task::Replace(e, Rc::from(v), Arg::Var(Rc::from(new_name))));
+                    } else {
+                        tasks.push(Task::Lambda(v));
+                        tasks.push(Task::Replace(e, param, arg));
+                    },
+                },
+                Task::ReplaceBuilt(param, arg) => {
+                    let e = built.pop().unwrap();
+                    tasks.push(Task::Replace(e, param, arg));
+                },
+                Task::Apply => {
+                    let e2 = built.pop().unwrap();
+                    let e1 = built.pop().unwrap();
+                    built.push(LambdaExpr::Apply(Box::new(e1), Box::new(e2)));
+                },
+                Task::Lambda(v) => {
+                    let e = built.pop().unwrap();
+                    built.push(LambdaExpr::Lambda(v, Box::new(e)));
+                },
             }
         }
+        built.pop().unwrap()
     }
 
     /// Performs beta-reduction on the first reducible term found.
     /// Second element of tuple is false if no reduction performed.
     pub fn beta(self) -> (LambdaExpr, bool) {
+        self.beta_with(Strategy::NormalOrder)
+    }
+
+    /// Performs beta-reduction on the redex chosen by `strategy`.
+    /// Second element of tuple is false if no reduction performed.
+    pub fn beta_with(mut self, strategy: Strategy) -> (LambdaExpr, bool) {
+        let path = match self.find_redex(strategy) {
+            Some(path) => path,
+            None => return (self, false),
+        };
+        let redex = self.subterm_mut(&path);
+        if let Node::Apply(e1, e2) = redex.take().into_node() {
+            if let Node::Lambda(v, e) = e1.into_node() {
+                *redex = e.replace(&v, &e2);
+                return (self, true);
+            }
+        }
+        unreachable!()
+    }
+
+    /// Path to the redex chosen by `strategy`, if any.
+    fn find_redex(&self, strategy: Strategy) -> Option<Vec<Step>> {
+        // `stack` holds the subterms being searched, with the number of
+        // their children searched so far; `path` leads to the last one
+        let mut stack = vec![(self, 0)];
+        let mut path = Vec::new();
+        while let Some(&mut (e, ref mut visited)) = stack.last_mut() {
+            let next = match e {
+                LambdaExpr::Variable(_) => None,
+                LambdaExpr::Lambda(_, body) =>
+                    if *visited == 0 && strategy.reduces_under_lambda() { Some((&**body, Step::Body)) } else { None },
+                LambdaExpr::Apply(e1, e2) => {
+                    let is_redex = matches!(**e1, LambdaExpr::Lambda(_, _));
+                    if strategy.is_eager() {
+                        // function and argument first, then the redex itself
+                        match *visited {
+                            0 => Some((&**e1, Step::Function)),
+                            1 => Some((&**e2, Step::Argument)),
+                            _ if is_redex => return Some(path),
+                            _ => None,
+                        }
+                    } else {
+                        // the redex itself first, then the function, then the argument
+                        match *visited {
+                            _ if is_redex => return Some(path),
+                            0 => Some((&**e1, Step::Function)),
+                            1 if strategy.reduces_arguments() => Some((&**e2, Step::Argument)),
+                            _ => None,
+                        }
+                    }
+                },
+            };
+            match next {
+                Some((child, step)) => {
+                    *visited += 1;
+                    stack.push((child, 0));
+                    path.push(step);
+                },
+                None => {
+                    stack.pop();
+                    path.pop();
+                },
+            }
+        }
+        None
+    }
+
+    /// Performs beta reduction up to `limit` times.
+    /// Returns the number of times reduced.
+    /// Will only be `limit` if no normal form found.
+    pub fn repeated_beta(self, limit: u32) -> (LambdaExpr, u32) {
+        self.repeated_beta_with(Strategy::NormalOrder, limit)
+    }
+
+    /// Performs beta reduction using `strategy` up to `limit` times.
+    /// Returns the number of times reduced.
+    /// Will only be `limit` if no normal form (for that strategy) found.
+    pub fn repeated_beta_with(self, strategy: Strategy, limit: u32) -> (LambdaExpr, u32) {
+        let mut ret = self;
+        for i in 0..limit {
+            let (next, res) = ret.beta_with(strategy);
+            if !res {
+                return (next, i)
+            }
+            ret = next;
+        }
+        (ret, limit)
+    }
+
+    /// Performs eta-reduction (`λx f x` to `f` where `x` is not free in `f`)
+    /// on the first reducible term found.
+    /// Second element of tuple is false if no reduction performed.
+    pub fn eta(mut self) -> (LambdaExpr, bool) {
+        let path = match self.find_first(LambdaExpr::is_eta_redex) {
+            Some(path) => path,
+            None => return (self, false),
+        };
+        let redex = self.subterm_mut(&path);
+        if let Node::Lambda(_, e) = redex.take().into_node() {
+            if let Node::Apply(f, _) = e.into_node() {
+                *redex = f;
+                return (self, true);
+            }
+        }
+        unreachable!()
+    }
+
+    fn is_eta_redex(&self) -> bool {
         match self {
-            LambdaExpr::Variable(v) => (LambdaExpr::Variable(v), false),
-            LambdaExpr::Lambda(v, e) => {
-                let (e, res) = e.beta();
-                (LambdaExpr::Lambda(v, Box::new(e)), res)
+            LambdaExpr::Lambda(v, body) => match **body {
+                LambdaExpr::Apply(ref f, ref x) => x.is_var(v) && !f.contains(v),
+                _ => false,
             },
-            LambdaExpr::Apply(e1, e2) => {
-                let (e1,) = (*e1,);
-                match e1 {
-                    LambdaExpr::Variable(v) => {
-                        let (e2, res) = e2.beta();
-                        (LambdaExpr::Apply(Box::new(LambdaExpr::Variable(v)), Box::new(e2)), res)
+            _ => false,
+        }
+    }
+
+    /// Path to the first subterm in pre-order for which `found` holds, if any.
+    pub(crate) fn find_first(&self, found: impl Fn(&LambdaExpr) -> bool) -> Option<Vec<Step>> {
+        // as in `find_redex`
+        let mut stack = vec![(self, 0)];
+        let mut path = Vec::new();
+        while let Some(&mut (e, ref mut visited)) = stack.last_mut() {
+            if *visited == 0 && found(e) {
+                return Some(path);
+            }
+            let next = match e {
+                LambdaExpr::Variable(_) => None,
+                LambdaExpr::Lambda(_, body) => if *visited == 0 { Some((&**body, Step::Body)) } else { None },
+                LambdaExpr::Apply(e1, e2) => match *visited {
+                    0 => Some((&**e1, Step::Function)),
+                    1 => Some((&**e2, Step::Argument)),
+                    _ => None,
+                },
+            };
+            match next {
+                Some((child, step)) => {
+                    *visited += 1;
+                    stack.push((child, 0));
+                    path.push(step);
+                },
+                None => {
+                    stack.pop();
+                    path.pop();
+                },
+            }
+        }
+        None
+    }
+
+    /// Subterm at the end of `path`.
+    pub(crate) fn subterm_mut(&mut self, path: &[Step]) -> &mut LambdaExpr {
+        let mut e = self;
+        for step in path {
+            e = match (e, step) {
+                (LambdaExpr::Apply(e1, _), Step::Function) => e1,
+                (LambdaExpr::Apply(_, e2), Step::Argument) => e2,
+                (LambdaExpr::Lambda(_, body), Step::Body) => body,
+                _ => panic!("path does not lead to a subterm"),
+            };
+        }
+        e
+    }
+
+    fn is_var(&self, var: &str) -> bool {
+        if let LambdaExpr::Variable(v) = self {
+            v == var
+        } else { false }
+    }
+
+    /// Performs eta-expansion, wrapping the term as `λx e x`
+    /// with `x` renamed if it is free in the term.
+    pub fn eta_expand(self) -> LambdaExpr {
+        let mut name = "x".to_string();
+        while self.contains(&name) {
+            name.push('\'');
+        }
+        LambdaExpr::Lambda(name.clone(), Box::new(LambdaExpr::Apply(Box::new(self), Box::new(LambdaExpr::Variable(name)))))
+    }
+
+    /// Performs a normal order beta-reduction, or an eta-reduction if no
+    /// beta-reduction is possible.
+    /// Second element of tuple is false if no reduction performed.
+    pub fn beta_eta(self) -> (LambdaExpr, bool) {
+        match self.beta() {
+            (e, true) => (e, true),
+            (e, false) => e.eta(),
+        }
+    }
+
+    /// Performs beta-eta reduction up to `limit` times.
+    /// Returns the number of times reduced.
+    /// Will only be `limit` if no beta-eta normal form found.
+    pub fn repeated_beta_eta(self, limit: u32) -> (LambdaExpr, u32) {
+        let mut ret = self;
+        for i in 0..limit {
+            let (next, res) = ret.beta_eta();
+            if !res {
+                return (next, i)
+            }
+            ret = next;
+        }
+        (ret, limit)
+    }
+}
+
+impl DbExpr {
+    /// Rebuilds the term with each index `i` replaced by `f(i, depth)`,
+    /// where `depth` is the number of lambdas around it within the term.
+    fn map_bound(self, mut f: impl FnMut(usize, usize) -> DbExpr) -> DbExpr {
+        enum Task {
+            Visit(DbExpr, usize),
+            Apply,
+            Lambda(String),
+        }
+        let mut tasks = vec![Task::Visit(self, 0)];
+        let mut built = Vec::new();
+        while let Some(task) = tasks.pop() {
+            match task {
+                Task::Visit(mut e, depth) => match e {
+                    DbExpr::Bound(i) => built.push(f(i, depth)),
+                    DbExpr::Free(ref mut v) => built.push(DbExpr::Free(mem::take(v))),
+                    DbExpr::Apply(ref mut e1, ref mut e2) => {
+                        tasks.push(Task::Apply);
+                        tasks.push(Task::Visit(e2.take(), depth));
+                        tasks.push(Task::Visit(e1.take(), depth));
                     },
-                    e1 @ LambdaExpr::Apply(_, _) => {
-                        let (e1, res) = e1.beta();
-                        let (e2, res) = if res { (*e2, res) } else { e2.beta() };
-                        (LambdaExpr::Apply(Box::new(e1), Box::new(e2)), res)
+                    DbExpr::Lambda(ref mut v, ref mut e) => {
+                        tasks.push(Task::Lambda(mem::take(v)));
+                        tasks.push(Task::Visit(e.take(), depth + 1));
                     },
-                    LambdaExpr::Lambda(v, e) => (e.replace(&v, &e2), true),
-                }
-            },
+                },
+                Task::Apply => {
+                    let e2 = built.pop().unwrap();
+                    let e1 = built.pop().unwrap();
+                    built.push(DbExpr::apply(e1, e2));
+                },
+                Task::Lambda(v) => {
+                    let e = built.pop().unwrap();
+                    built.push(DbExpr::Lambda(v, Box::new(e)));
+                },
+            }
+        }
+        built.pop().unwrap()
+    }
+
+    /// Adds `amount` to every index that refers to a binder outside
+    /// the innermost `cutoff` lambdas.
+    /// Panics if that makes an index negative.
+    pub fn shift(self, amount: isize, cutoff: usize) -> DbExpr {
+        self.map_bound(|i, depth| DbExpr::Bound(if i < cutoff + depth {
+            i
+        } else if amount < 0 {
+            i.checked_sub(amount.unsigned_abs()).expect("index shifted below zero")
+        } else {
+            i + amount as usize
+        }))
+    }
+
+    /// Replaces index `index` with `arg`, adjusting the free indices of
+    /// `arg` as it moves under binders. No renaming is ever needed.
+    pub fn subst(self, index: usize, arg: &DbExpr) -> DbExpr {
+        self.map_bound(|i, depth| if i == index + depth {
+            arg.clone().shift(depth as isize, 0)
+        } else {
+            DbExpr::Bound(i)
+        })
+    }
+
+    /// Performs beta-reduction on the leftmost-outermost redex.
+    /// Second element of tuple is false if no reduction performed.
+    pub fn beta(mut self) -> (DbExpr, bool) {
+        let path = match self.find_redex() {
+            Some(path) => path,
+            None => return (self, false),
+        };
+        let redex = self.subterm_mut(&path);
+        if let DbExpr::Apply(ref mut e1, ref mut e2) = *redex {
+            if let DbExpr::Lambda(_, ref mut e) = **e1 {
+                let reduced = e.take().subst(0, &e2.take().shift(1, 0)).shift(-1, 0);
+                *redex = reduced;
+                return (self, true);
+            }
         }
+        unreachable!()
+    }
+
+    /// Path to the leftmost-outermost redex, if any.
+    fn find_redex(&self) -> Option<Vec<Step>> {
+        // subterms still to search, with the length of the path to their
+        // parent and the step from it; `path` leads to the last one popped
+        let mut stack = vec![(self, 0, None)];
+        let mut path = Vec::new();
+        while let Some((e, parent, step)) = stack.pop() {
+            path.truncate(parent);
+            path.extend(step);
+            match e {
+                DbExpr::Apply(e1, _) if matches!(**e1, DbExpr::Lambda(..)) => return Some(path),
+                DbExpr::Apply(e1, e2) => {
+                    stack.push((e2, path.len(), Some(Step::Argument)));
+                    stack.push((e1, path.len(), Some(Step::Function)));
+                },
+                DbExpr::Lambda(_, e) => stack.push((e, path.len(), Some(Step::Body))),
+                DbExpr::Free(_) | DbExpr::Bound(_) => {},
+            }
+        }
+        None
+    }
+
+    /// Subterm at the end of `path`.
+    fn subterm_mut(&mut self, path: &[Step]) -> &mut DbExpr {
+        let mut e = self;
+        for step in path {
+            e = match (e, step) {
+                (DbExpr::Apply(e1, _), Step::Function) => e1,
+                (DbExpr::Apply(_, e2), Step::Argument) => e2,
+                (DbExpr::Lambda(_, body), Step::Body) => body,
+                _ => panic!("path does not lead to a subterm"),
+            };
+        }
+        e
     }
 
     /// Performs beta reduction up to `limit` times.
     /// Returns the number of times reduced.
     /// Will only be `limit` if no normal form found.
-    pub fn repeated_beta(self, limit: u32) -> (LambdaExpr, u32) {
+    pub fn repeated_beta(self, limit: u32) -> (DbExpr, u32) {
         let mut ret = self;
         for i in 0..limit {
             let (next, res) = ret.beta();
@@ -72,9 +526,109 @@ impl LambdaExpr {
     }
 }
 
+impl SKIExpr {
+    /// Whether this term is `I x`, `K x y` or `S x y z`.
+    fn is_redex(&self) -> bool {
+        if let SKIExpr::Apply(f, _) = self {
+            match **f {
+                SKIExpr::I => true,
+                SKIExpr::Apply(ref f, _) => match **f {
+                    SKIExpr::K => true,
+                    SKIExpr::Apply(ref f, _) => **f == SKIExpr::S,
+                    _ => false,
+                },
+                _ => false,
+            }
+        } else { false }
+    }
+
+    /// Contracts a term for which `is_redex` holds.
+    fn contract(self) -> SKIExpr {
+        let (f, x) = self.unapply().unwrap();
+        let (f, y) = match f {
+            SKIExpr::I => return x,
+            f => f.unapply().unwrap(),
+        };
+        match f {
+            SKIExpr::K => y,
+            f => {
+                let (_, z) = f.unapply().unwrap();
+                SKIExpr::apply(SKIExpr::apply(z, x.clone()), SKIExpr::apply(y, x))
+            },
+        }
+    }
+
+    /// Performs a combinator reduction on the leftmost-outermost redex.
+    /// Second element of tuple is false if no reduction performed.
+    pub fn reduce(self) -> (SKIExpr, bool) {
+        self.reduce_with(Strategy::NormalOrder)
+    }
+
+    /// Performs a combinator reduction on the redex chosen by `strategy`.
+    /// Since there are no lambdas, call-by-name behaves as normal order,
+    /// call-by-value as applicative order, and head as weak head.
+    /// Second element of tuple is false if no reduction performed.
+    pub fn reduce_with(self, strategy: Strategy) -> (SKIExpr, bool) {
+        if !strategy.is_eager() && self.is_redex() {
+            return (self.contract(), true);
+        }
+        let (e1, e2) = match self {
+            SKIExpr::Apply(..) => self.unapply().unwrap(),
+            e => return (e, false),
+        };
+        let (e1, res) = e1.reduce_with(strategy);
+        if res {
+            return (SKIExpr::apply(e1, e2), true);
+        }
+        let (e2, res) = if strategy.reduces_arguments() { e2.reduce_with(strategy) } else { (e2, false) };
+        let e = SKIExpr::apply(e1, e2);
+        if !res && strategy.is_eager() && e.is_redex() {
+            (e.contract(), true)
+        } else {
+            (e, res)
+        }
+    }
+
+    /// Performs combinator reduction up to `limit` times.
+    /// Returns the number of times reduced.
+    /// Will only be `limit` if no normal form found.
+    pub fn repeated_reduce(self, limit: u32) -> (SKIExpr, u32) {
+        self.repeated_reduce_with(Strategy::NormalOrder, limit)
+    }
+
+    /// Performs combinator reduction using `strategy` up to `limit` times.
+    /// Returns the number of times reduced.
+    /// Will only be `limit` if no normal form (for that strategy) found.
+    pub fn repeated_reduce_with(self, strategy: Strategy, limit: u32) -> (SKIExpr, u32) {
+        let mut ret = self;
+        for i in 0..limit {
+            let (next, res) = ret.reduce_with(strategy);
+            if !res {
+                return (next, i)
+            }
+            ret = next;
+        }
+        (ret, limit)
+    }
+}
+
+impl IotaExpr {
+    /// Evaluates the program by translating `ι` to `S (S I (K S)) (K K)` and
+    /// performing normal order combinator reduction up to `limit` times.
+    /// Returns the number of times reduced.
+    pub fn eval(self, limit: u32) -> (SKIExpr, u32) {
+        SKIExpr::from(self).repeated_reduce(limit)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use expr::lambda::*;
+    use interpret::*;
+    use expr::debruijn::DbExpr;
+    use expr::ski::SKIExpr;
+    use expr::iota;
+    use encoding::{numeral, Encoding};
 
     #[test]
     fn replace_alpha() {
@@ -87,8 +641,7 @@ mod tests {
     }
 
     #[test]
-    fn beta_eta() {
-        // eta-conversion
+    fn beta_discard() {
         assert_eq!((parse("y").unwrap(), true), parse("(\\x y) x").unwrap().beta());
     }
 
@@ -101,4 +654,188 @@ mod tests {
     fn beta_succ() {
         assert_eq!((parse("\\f \\x f (f x)").unwrap(), 3), parse("(\\n \\f \\x f (n f x)) (\\f \\x f x)").unwrap().repeated_beta(10));
     }
+
+    #[test]
+    fn deep_terms() {
+        // deeper than the recursion the default test thread stack allows
+        let n = 300000;
+        let deep = numeral(n, Encoding::Church);
+        let mut redex = parse("(\\y y) x").unwrap();
+        for _ in 0..n {
+            redex = LambdaExpr::apply(LambdaExpr::Variable("f".to_string()), redex);
+        }
+        let (reduced, res) = LambdaExpr::Lambda("f".to_string(), Box::new(LambdaExpr::Lambda("x".to_string(), Box::new(redex)))).beta();
+        assert!(res);
+        assert_eq!(deep, reduced);
+        let spine = |x: &str| (0..n).fold(LambdaExpr::Variable(x.to_string()),
+            |e, _| LambdaExpr::apply(LambdaExpr::Variable("f".to_string()), e));
+        assert!(spine("x").contains("x") && !deep.contains("x"));
+        assert_eq!(spine("y"), spine("x").replace("x", &parse("y").unwrap()));
+        assert_eq!(format!("λf λx {}x{}", "f (".repeat(n as usize - 1) + "f ", ")".repeat(n as usize - 1)), deep.to_string());
+        assert_eq!((deep.clone(), false), deep.clone().eta());
+        assert_eq!(deep, LambdaExpr::from(DbExpr::from(deep.clone())));
+        assert!(SKIExpr::from_lambda(deep).is_ok());
+    }
+
+    #[test]
+    fn deep_de_bruijn_terms() {
+        // deeper than the recursion the default test thread stack allows
+        let n = 300000;
+        let deep = DbExpr::from(numeral(n, Encoding::Church));
+        let lam = |v: &str, e: LambdaExpr| LambdaExpr::Lambda(v.to_string(), Box::new(e));
+        let spine = |inner: LambdaExpr| (0..n).fold(inner,
+            |e, _| LambdaExpr::apply(LambdaExpr::Variable("f".to_string()), e));
+        // redex at the bottom of the term, and a deep term substituted into
+        let inner_redex = DbExpr::from(lam("f", lam("x", spine(parse("(\\y y) x").unwrap()))));
+        let outer_redex = DbExpr::from(lam("f", lam("y", LambdaExpr::apply(lam("x", spine(parse("x").unwrap())), parse("y").unwrap()))));
+        assert!(deep != inner_redex && deep != outer_redex);
+        assert_eq!((deep.clone(), true), inner_redex.beta());
+        assert_eq!((deep.clone(), true), outer_redex.beta());
+        assert_eq!((deep.clone(), false), deep.clone().beta());
+        assert_eq!(deep, deep.clone().shift(1, 0));
+        assert_eq!(format!("λ λ {}0{}", "1 (".repeat(n as usize - 1) + "1 ", ")".repeat(n as usize - 1)), deep.to_string());
+    }
+
+    #[test]
+    fn eta_simple() {
+        assert_eq!((parse("f").unwrap(), true), parse("\\x f x").unwrap().eta());
+        assert_eq!((parse("\\x x x").unwrap(), false), parse("\\x x x").unwrap().eta());
+    }
+
+    #[test]
+    fn eta_nested() {
+        assert_eq!((parse("f").unwrap(), 2), parse("\\x \\y f x y").unwrap().repeated_beta_eta(10));
+        assert_eq!((parse("g (f y)").unwrap(), 1), parse("g (\\x f y x)").unwrap().repeated_beta_eta(10));
+    }
+
+    #[test]
+    fn eta_expand() {
+        assert_eq!(parse("\\x f x").unwrap(), parse("f").unwrap().eta_expand());
+        assert_eq!(parse("\\x' x x'").unwrap(), parse("x").unwrap().eta_expand());
+        assert_eq!((parse("f").unwrap(), true), parse("f").unwrap().eta_expand().eta());
+    }
+
+    #[test]
+    fn beta_eta_church_one() {
+        // succ 0 = λf λx f x, which eta-reduces to λf f
+        let e = parse("(\\n \\f \\x f (n f x)) (\\f \\x x)").unwrap();
+        assert_eq!((parse("\\f f").unwrap(), 4), e.repeated_beta_eta(10));
+    }
+
+    #[test]
+    fn strategy_omega_argument() {
+        let e = parse("(\\x y) ((\\x x x) (\\x x x))").unwrap();
+        assert_eq!((parse("y").unwrap(), 1), e.clone().repeated_beta_with(Strategy::NormalOrder, 10));
+        assert_eq!((parse("y").unwrap(), 1), e.clone().repeated_beta_with(Strategy::CallByName, 10));
+        assert_eq!(10, e.clone().repeated_beta_with(Strategy::ApplicativeOrder, 10).1);
+        assert_eq!(10, e.repeated_beta_with(Strategy::CallByValue, 10).1);
+    }
+
+    #[test]
+    fn strategy_weak() {
+        let e = parse("\\y (\\x x) y").unwrap();
+        assert_eq!((e.clone(), 0), e.clone().repeated_beta_with(Strategy::WeakHead, 10));
+        assert_eq!((e.clone(), 0), e.clone().repeated_beta_with(Strategy::CallByValue, 10));
+        assert_eq!((parse("\\y y").unwrap(), 1), e.repeated_beta_with(Strategy::Head, 10));
+    }
+
+    #[test]
+    fn strategy_head_arguments() {
+        let e = parse("x ((\\y y) z)").unwrap();
+        assert_eq!((e.clone(), 0), e.clone().repeated_beta_with(Strategy::Head, 10));
+        assert_eq!((parse("x z").unwrap(), 1), e.clone().repeated_beta_with(Strategy::CallByName, 10));
+        assert_eq!((parse("x z").unwrap(), 1), e.repeated_beta_with(Strategy::NormalOrder, 10));
+    }
+
+    #[test]
+    fn strategy_from_str() {
+        for st in Strategy::all() {
+            assert_eq!(Ok(*st), st.name().parse());
+        }
+        assert!("lazy".parse::<Strategy>().is_err());
+    }
+
+    #[test]
+    fn debruijn_beta_no_capture() {
+        // `replace` would rename `y` to the already used `y'`
+        let e = DbExpr::from(parse("(\\x \\y x y') y").unwrap());
+        let (e, n) = e.repeated_beta(10);
+        assert_eq!(1, n);
+        assert_eq!("λy1 y y'", format!("{}", LambdaExpr::from(e)));
+    }
+
+    #[test]
+    fn debruijn_beta_succ() {
+        let (e, n) = DbExpr::from(parse("(\\n \\f \\x f (n f x)) (\\f \\x f x)").unwrap()).repeated_beta(10);
+        assert_eq!((DbExpr::from(parse("\\f \\x f (f x)").unwrap()), 3), (e, n));
+    }
+
+    #[test]
+    fn debruijn_beta_under_binder() {
+        let (e, _) = DbExpr::from(parse("\\z (\\x \\y x) z").unwrap()).repeated_beta(10);
+        assert_eq!("λ λ 1", format!("{}", e));
+    }
+
+    #[test]
+    fn debruijn_subst_outer_index() {
+        // `1 (λ 2)` with 1 replaced by 0: under the lambda, both are one higher
+        let e = DbExpr::apply(DbExpr::Bound(1), DbExpr::Lambda("x".to_string(), Box::new(DbExpr::Bound(2))));
+        let expected = DbExpr::apply(DbExpr::Bound(0), DbExpr::Lambda("x".to_string(), Box::new(DbExpr::Bound(1))));
+        assert_eq!(expected, e.subst(1, &DbExpr::Bound(0)));
+    }
+
+    #[test]
+    #[should_panic(expected = "index shifted below zero")]
+    fn debruijn_shift_below_zero() {
+        DbExpr::Bound(0).shift(-1, 0);
+    }
+
+    #[test]
+    fn ski_reduce_combinators() {
+        assert_eq!((SKIExpr::S, true), SKIExpr::apply(SKIExpr::I, SKIExpr::S).reduce());
+        assert_eq!((SKIExpr::S, true), SKIExpr::apply(SKIExpr::apply(SKIExpr::K, SKIExpr::S), SKIExpr::I).reduce());
+        assert_eq!((SKIExpr::S, 2), SKIExpr::apply(SKIExpr::apply(SKIExpr::apply(SKIExpr::S, SKIExpr::K), SKIExpr::K), SKIExpr::S).repeated_reduce(10));
+    }
+
+    #[test]
+    fn ski_reduce_from_lambda() {
+        // (λx λy y x) K S = S K
+        let flip = SKIExpr::from_lambda(parse("\\x \\y y x").unwrap()).unwrap();
+        let (e, _) = SKIExpr::apply(SKIExpr::apply(flip, SKIExpr::K), SKIExpr::S).repeated_reduce(100);
+        assert_eq!(SKIExpr::apply(SKIExpr::S, SKIExpr::K), e);
+    }
+
+    #[test]
+    fn ski_reduce_strategies() {
+        let omega = SKIExpr::apply(SKIExpr::apply(SKIExpr::apply(SKIExpr::S, SKIExpr::I), SKIExpr::I), SKIExpr::apply(SKIExpr::apply(SKIExpr::S, SKIExpr::I), SKIExpr::I));
+        let e = SKIExpr::apply(SKIExpr::apply(SKIExpr::K, SKIExpr::I), omega);
+        assert_eq!((SKIExpr::I, 1), e.clone().repeated_reduce_with(Strategy::NormalOrder, 100));
+        assert_eq!(100, e.repeated_reduce_with(Strategy::ApplicativeOrder, 100).1);
+    }
+
+    #[test]
+    fn ski_reduce_weak_head() {
+        let e = SKIExpr::apply(SKIExpr::S, SKIExpr::apply(SKIExpr::I, SKIExpr::K));
+        assert_eq!((e.clone(), 0), e.clone().repeated_reduce_with(Strategy::WeakHead, 10));
+        assert_eq!((SKIExpr::apply(SKIExpr::S, SKIExpr::K), 1), e.repeated_reduce_with(Strategy::NormalOrder, 10));
+    }
+
+    #[test]
+    fn iota_eval() {
+        // ι ι = S K (K K), which behaves as I
+        let (e, _) = iota::parse("*ii").unwrap().eval(100);
+        assert_eq!(SKIExpr::apply(SKIExpr::apply(SKIExpr::S, SKIExpr::K), SKIExpr::apply(SKIExpr::K, SKIExpr::K)), e);
+        let (e, _) = SKIExpr::apply(SKIExpr::from(iota::parse("*ii").unwrap()), SKIExpr::S).repeated_reduce(100);
+        assert_eq!(SKIExpr::S, e);
+    }
+
+    #[test]
+    fn iota_eval_lambda2iota() {
+        // (λx λy x) as printed by lambda2iota, applied to K and S
+        let k = iota::IotaExpr::from(SKIExpr::from_lambda(parse("\\x \\y x").unwrap()).unwrap()).to_string();
+        let prog = iota::parse(&format!("**{}{}{}", k, iota::IotaExpr::from(SKIExpr::K).to_string(), iota::IotaExpr::from(SKIExpr::S).to_string())).unwrap();
+        let (e, _) = prog.eval(1000);
+        let (k, _) = SKIExpr::from(iota::IotaExpr::from(SKIExpr::K)).repeated_reduce(1000);
+        assert_eq!(k, e);
+    }
 }
@@ -0,0 +1,210 @@
+use expr::debruijn::DbExpr;
+use expr::lambda::LambdaExpr;
+use env::{extend, lookup, Env, Level};
+
+/// Term paired with the environment it is evaluated in.
+#[derive(Clone)]
+enum Closure<'a> {
+    Term(&'a DbExpr, Env<Closure<'a>>),
+    Level(Level),
+}
+
+/// Weak head normal form reached by the machine.
+enum Whnf<'a> {
+    /// Lambda with its name hint and body, and no argument to apply it to.
+    Lambda(&'a str, &'a DbExpr, Env<Closure<'a>>),
+    /// Variable applied to arguments, first argument first.
+    Neutral(DbExpr, Vec<Closure<'a>>),
+}
+
+struct Machine {
+    steps: u32,
+    limit: u32,
+}
+
+impl Machine {
+    /// Runs the machine to weak head normal form by call-by-name, or returns
+    /// `None` if that takes more than the remaining steps.
+    fn whnf<'a>(&mut self, closure: Closure<'a>, depth: usize) -> Option<Whnf<'a>> {
+        let (mut term, mut env) = match closure {
+            Closure::Term(term, env) => (term, env),
+            Closure::Level(level) => return Some(Whnf::Neutral(level.at(depth), Vec::new())),
+        };
+        // arguments, the next one to apply on top
+        let mut stack = Vec::new();
+        loop {
+            match term {
+                DbExpr::Apply(e1, e2) => {
+                    stack.push(Closure::Term(e2, env.clone()));
+                    term = e1;
+                },
+                DbExpr::Lambda(hint, body) => match stack.pop() {
+                    Some(arg) => {
+                        if self.steps == self.limit {
+                            return None;
+                        }
+                        self.steps += 1;
+                        env = extend(&env, arg);
+                        term = body;
+                    },
+                    None => return Some(Whnf::Lambda(hint, body, env)),
+                },
+                DbExpr::Bound(i) => match lookup(&env, *i) {
+                    Closure::Term(t, e) => {
+                        term = t;
+                        env = e;
+                    },
+                    Closure::Level(level) => {
+                        stack.reverse();
+                        return Some(Whnf::Neutral(level.at(depth), stack));
+                    },
+                },
+                DbExpr::Free(v) => {
+                    stack.reverse();
+                    return Some(Whnf::Neutral(DbExpr::Free(v.clone()), stack));
+                },
+            }
+        }
+    }
+
+    /// Converts a weak head normal form at `depth` nested lambdas to a term,
+    /// either substituting closures without evaluating them (`normalize`
+    /// false), or evaluating them to normal form: to weak head normal form
+    /// first, then under the lambda or in the arguments, left to right.
+    fn readback<'a>(&mut self, whnf: Whnf<'a>, depth: usize, normalize: bool) -> Option<DbExpr> {
+        enum Task<'a> {
+            Read(Closure<'a>, usize),
+            Whnf(Whnf<'a>, usize),
+            Apply,
+            Lambda(&'a str),
+        }
+        let mut tasks = vec![Task::Whnf(whnf, depth)];
+        let mut built = Vec::new();
+        while let Some(task) = tasks.pop() {
+            match task {
+                Task::Read(closure, depth) => {
+                    let whnf = if normalize {
+                        self.whnf(closure, depth)?
+                    } else {
+                        match closure {
+                            Closure::Term(DbExpr::Lambda(hint, body), env) => Whnf::Lambda(hint, body, env),
+                            Closure::Term(DbExpr::Apply(e1, e2), env) => {
+                                tasks.push(Task::Apply);
+                                tasks.push(Task::Read(Closure::Term(e2, env.clone()), depth));
+                                tasks.push(Task::Read(Closure::Term(e1, env), depth));
+                                continue
+                            },
+                            Closure::Term(DbExpr::Bound(i), env) => {
+                                tasks.push(Task::Read(lookup(&env, *i), depth));
+                                continue
+                            },
+                            Closure::Term(DbExpr::Free(v), _) => Whnf::Neutral(DbExpr::Free(v.clone()), Vec::new()),
+                            Closure::Level(level) => Whnf::Neutral(level.at(depth), Vec::new()),
+                        }
+                    };
+                    tasks.push(Task::Whnf(whnf, depth));
+                },
+                Task::Whnf(Whnf::Lambda(hint, body, env), depth) => {
+                    tasks.push(Task::Lambda(hint));
+                    tasks.push(Task::Read(Closure::Term(body, extend(&env, Closure::Level(Level(depth)))), depth + 1));
+                },
+                Task::Whnf(Whnf::Neutral(head, args), depth) => {
+                    built.push(head);
+                    for arg in args.into_iter().rev() {
+                        tasks.push(Task::Apply);
+                        tasks.push(Task::Read(arg, depth));
+                    }
+                },
+                Task::Apply => {
+                    let e2 = built.pop().unwrap();
+                    let e1 = built.pop().unwrap();
+                    built.push(DbExpr::Apply(Box::new(e1), Box::new(e2)));
+                },
+                Task::Lambda(hint) => {
+                    let e = built.pop().unwrap();
+                    built.push(DbExpr::Lambda(hint.to_string(), Box::new(e)));
+                },
+            }
+        }
+        built.pop()
+    }
+}
+
+fn run(expr: &LambdaExpr, limit: u32, normalize: bool) -> Option<(LambdaExpr, u32)> {
+    let expr = DbExpr::from(expr.clone());
+    let mut machine = Machine { steps: 0, limit };
+    let whnf = machine.whnf(Closure::Term(&expr, None), 0)?;
+    let result = machine.readback(whnf, 0, normalize)?;
+    Some((LambdaExpr::from(result), machine.steps))
+}
+
+/// Weak head normal form of `expr` by the Krivine machine, i.e. call-by-name
+/// without reducing under lambdas or in arguments, along with the number of
+/// beta-reductions performed; `None` if more than `limit` are needed.
+pub fn whnf(expr: &LambdaExpr, limit: u32) -> Option<(LambdaExpr, u32)> {
+    run(expr, limit, false)
+}
+
+/// Normal form of `expr`, by running the Krivine machine to weak head normal
+/// form and then again under each lambda and in each argument of a variable.
+/// The redexes are those normal order reduction contracts, in the same order,
+/// so the number of beta-reductions matches `LambdaExpr::repeated_beta`;
+/// `None` if more than `limit` are needed.
+pub fn normalize(expr: &LambdaExpr, limit: u32) -> Option<(LambdaExpr, u32)> {
+    run(expr, limit, true)
+}
+
+#[cfg(test)]
+mod tests {
+    use krivine::*;
+    use expr::lambda::parse;
+    use encoding::{numeral, Encoding};
+    use prelude;
+
+    #[test]
+    fn whnf_stops_at_lambda() {
+        assert_eq!(Some((parse("\\y (\\x x) y").unwrap(), 1)), whnf(&parse("(\\z \\y z y) (\\x x)").unwrap(), 10));
+        // arguments of a variable are left alone
+        assert_eq!(Some((parse("f ((\\x x) y)").unwrap(), 1)), whnf(&parse("(\\z f z) ((\\x x) y)").unwrap(), 10));
+        assert_eq!(None, whnf(&parse("(\\x x x) (\\x x x)").unwrap(), 100));
+    }
+
+    #[test]
+    fn whnf_no_capture() {
+        let (e, _) = whnf(&parse("(\\x \\y x) y").unwrap(), 10).unwrap();
+        assert_eq!(parse("\\y1 y").unwrap(), e);
+    }
+
+    #[test]
+    fn normalize_simple() {
+        assert_eq!(Some((parse("\\f \\x f (f x)").unwrap(), 3)),
+            normalize(&parse("(\\n \\f \\x f (n f x)) (\\f \\x f x)").unwrap(), 10));
+        assert_eq!(Some((parse("\\y g (f y)").unwrap(), 2)), normalize(&parse("\\y g ((\\x f x) ((\\z z) y))").unwrap(), 10));
+        // the unused argument is never evaluated
+        assert_eq!(Some((parse("y").unwrap(), 1)), normalize(&parse("(\\x y) ((\\x x x) (\\x x x))").unwrap(), 10));
+        assert_eq!(None, normalize(&parse("\\y (\\x x x) (\\x x x)").unwrap(), 100));
+    }
+
+    #[test]
+    fn normalize_matches_normal_order() {
+        for s in &["mul (exp 2 3) (pred 4)", "foldr add 0 (map succ [1, 2, 3])", "\\b not (and b true)", "sub 5 3 (\\x x)"] {
+            let e = prelude::parse(s).unwrap();
+            let (expected, steps) = e.clone().repeated_beta(100000);
+            let (result, krivine_steps) = normalize(&e, 100000).unwrap();
+            assert!(result.alpha_eq(&expected), "{}: {} vs {}", s, result, expected);
+            assert_eq!(steps, krivine_steps, "{}", s);
+        }
+    }
+
+    #[test]
+    fn normalize_factorial() {
+        let e = parse(include_str!("../factorial.lambda")).unwrap();
+        let e = LambdaExpr::apply(e, numeral(5, Encoding::Church));
+        // as many steps as `repeated_beta`, which takes seconds
+        let (e, steps) = normalize(&e, 100000).unwrap();
+        assert!(e.alpha_eq(&numeral(120, Encoding::Church)));
+        assert_eq!(27112, steps);
+        // a normal form takes no steps
+        assert_eq!(Some((e.clone(), 0)), normalize(&e, 0));
+    }
+}
@@ -1,3 +1,14 @@
 pub mod expr;
 pub mod convert;
 pub mod interpret;
+pub mod alpha;
+pub mod decompile;
+pub mod program;
+pub mod prelude;
+pub mod encoding;
+pub mod readback;
+pub mod types;
+mod env;
+pub mod graph;
+pub mod krivine;
+pub mod cek;
@@ -0,0 +1,178 @@
+use encoding::Encoding;
+use expr::lambda;
+use expr::lambda::{bind_definitions, parse_program, parse_program_with, Definition, LambdaExpr, ParseError};
+
+/// Name under which files can `import` the prelude.
+pub const NAME: &str = "prelude";
+
+/// Source of the prelude: booleans, pairs, Church numerals with arithmetic
+/// and comparison, lists, and the Y and Z combinators.
+pub const SOURCE: &str = include_str!("prelude.lambda");
+
+/// Scott-encoded numerals and lists, defining the same names as those of `SOURCE`.
+pub const SCOTT_SOURCE: &str = include_str!("prelude_scott.lambda");
+
+/// Definitions of the prelude, in source order.
+pub fn definitions() -> Vec<Definition> {
+    parse_program(SOURCE).expect("prelude should parse").definitions
+}
+
+/// Definitions of the prelude with numerals and lists in the given encoding,
+/// so that they work on literals in that encoding.
+pub fn definitions_with(encoding: Encoding) -> Vec<Definition> {
+    match encoding {
+        Encoding::Church => definitions(),
+        Encoding::Scott => {
+            let scott = parse_program_with(SCOTT_SOURCE, encoding).expect("Scott prelude should parse").definitions;
+            let mut definitions: Vec<Definition> = definitions().into_iter()
+                .filter(|def| scott.iter().all(|d| d.name != def.name))
+                .collect();
+            definitions.extend(scott);
+            definitions
+        },
+    }
+}
+
+/// Like `expr::lambda::parse`, but with the prelude in scope.
+/// Definitions in `s` shadow those of the prelude.
+pub fn parse(s: &str) -> Result<LambdaExpr, ParseError> {
+    parse_with(s, Encoding::Church)
+}
+
+/// Like `parse`, with literals and the prelude in the given encoding.
+pub fn parse_with(s: &str, encoding: Encoding) -> Result<LambdaExpr, ParseError> {
+    let program = parse_program_with(s, encoding)?;
+    if !program.imports.is_empty() || program.body.is_none() {
+        // let the plain parser report the error
+        return lambda::parse(s);
+    }
+    let mut definitions = definitions_with(encoding);
+    definitions.extend(program.definitions);
+    Ok(bind_definitions(&definitions, program.body.unwrap()))
+}
+
+#[cfg(test)]
+mod tests {
+    use prelude::*;
+    use encoding::Decode;
+    use expr::debruijn::DbExpr;
+
+    fn closed(e: &DbExpr) -> bool {
+        match e {
+            DbExpr::Free(_) => false,
+            DbExpr::Bound(_) => true,
+            DbExpr::Apply(e1, e2) => closed(e1) && closed(e2),
+            DbExpr::Lambda(_, e) => closed(e),
+        }
+    }
+
+    fn eval(s: &str) -> LambdaExpr {
+        let (e, steps) = parse(s).unwrap().repeated_beta(100000);
+        assert!(steps < 100000, "{} did not normalize", s);
+        e
+    }
+
+    fn numeral(n: usize) -> LambdaExpr {
+        lambda::parse(&format!("\\f \\x {}x{}", "f (".repeat(n), ")".repeat(n))).unwrap()
+    }
+
+    fn assert_evaluates(expected: &str, s: &str) {
+        let e = eval(s);
+        assert!(e.alpha_eq(&eval(expected)), "{} evaluated to {}, expected {}", s, e, expected);
+    }
+
+    #[test]
+    fn closed_definitions() {
+        // definitions only use those above them
+        for &enc in Encoding::all() {
+            for def in definitions_with(enc) {
+                assert!(closed(&DbExpr::from(parse_with(&def.name, enc).unwrap())), "{} is not closed", def.name);
+            }
+        }
+    }
+
+    #[test]
+    fn booleans() {
+        assert_evaluates("false", "not true");
+        assert_evaluates("true", "and true (or false true)");
+        assert_evaluates("false", "xor true true");
+        assert_evaluates("a", "if false b a");
+    }
+
+    #[test]
+    fn pairs() {
+        assert_evaluates("a", "fst (pair a b)");
+        assert_evaluates("b", "snd (pair a b)");
+    }
+
+    #[test]
+    fn arithmetic() {
+        assert!(eval("add two (succ two)").alpha_eq(&numeral(5)));
+        assert!(eval("mul two (add two one)").alpha_eq(&numeral(6)));
+        assert!(eval("pred (exp two (succ two))").alpha_eq(&numeral(7)));
+        assert!(eval("sub (mul two two) one").alpha_eq(&numeral(3)));
+        assert!(eval("sub one two").alpha_eq(&numeral(0)));
+        assert!(eval("pred zero").alpha_eq(&numeral(0)));
+    }
+
+    #[test]
+    fn comparison() {
+        assert_evaluates("true", "iszero zero");
+        assert_evaluates("false", "iszero one");
+        assert_evaluates("true", "leq one two");
+        assert_evaluates("false", "gt one two");
+        assert_evaluates("true", "eq (add one one) two");
+        assert_evaluates("false", "lt two two");
+        assert_evaluates("true", "geq two two");
+    }
+
+    #[test]
+    fn lists() {
+        assert_evaluates("cons a (cons b nil)", "append (cons a nil) (cons b nil)");
+        assert_evaluates("cons (f a) (cons (f b) nil)", "map f (cons a (cons b nil))");
+        assert_evaluates("cons b nil", "tail (cons a (cons b nil))");
+        assert_evaluates("a", "head (cons a nil)");
+        assert_evaluates("true", "isnil (tail (cons a nil))");
+        assert!(eval("length (cons a (cons b nil))").alpha_eq(&numeral(2)));
+    }
+
+    #[test]
+    fn recursion() {
+        let fact = "fact = Y (\\f \\n if (iszero n) one (mul n (f (pred n))))\n";
+        assert!(eval(&format!("{}fact (succ two)", fact)).alpha_eq(&numeral(6)));
+        let (e, _) = parse("Z (\\f \\n iszero n zero (f (pred n))) two").unwrap()
+            .repeated_beta_with(::interpret::Strategy::CallByValue, 10000);
+        let (e, _) = e.repeated_beta(1000);
+        assert!(e.alpha_eq(&numeral(0)));
+    }
+
+    #[test]
+    fn shadowing() {
+        assert_evaluates("b", "true a b = b\ntrue a b");
+    }
+
+    #[test]
+    fn scott() {
+        // the same names, with numerals and lists replaced
+        let names = |enc| {
+            let mut names: Vec<String> = definitions_with(enc).into_iter().map(|d| d.name).collect();
+            names.sort();
+            names
+        };
+        assert_eq!(names(Encoding::Church), names(Encoding::Scott));
+        let eval = |s: &str| {
+            let (e, steps) = parse_with(s, Encoding::Scott).unwrap().repeated_beta(100000);
+            assert!(steps < 100000, "{} did not normalize", s);
+            e
+        };
+        assert_eq!(Some(3), u64::decode(&eval("add 1 2"), Encoding::Scott));
+        assert_eq!(Some(6), u64::decode(&eval("mul 2 (sub 5 2)"), Encoding::Scott));
+        assert_eq!(Some(8), u64::decode(&eval("exp 2 3"), Encoding::Scott));
+        assert_eq!(Some(1), u64::decode(&eval("pred (pred 3)"), Encoding::Scott));
+        assert_eq!(Some(true), bool::decode(&eval("leq 2 3"), Encoding::Scott));
+        assert_eq!(Some(false), bool::decode(&eval("eq 2 3"), Encoding::Scott));
+        assert_eq!(Some(vec![2, 3, 1]), Vec::<u64>::decode(&eval("append (map succ [1, 2]) (tail [5, 1])"), Encoding::Scott));
+        assert_eq!(Some(6), u64::decode(&eval("foldr add 0 [1, 2, 3]"), Encoding::Scott));
+        assert_eq!(Some(2), u64::decode(&eval("length [a, b]"), Encoding::Scott));
+    }
+}
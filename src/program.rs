@@ -0,0 +1,190 @@
+use encoding::Encoding;
+use expr::lambda::{parse_program_with, Definition, ParseError, Program};
+use prelude;
+
+use std::collections::HashSet;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+#[derive(Debug)]
+pub enum LoadError {
+    Io(PathBuf, io::Error),
+    /// Syntax error, along with the contents of the file for `ParseError::render`.
+    Syntax(PathBuf, String, ParseError),
+    /// Files that import each other, starting and ending with the same file.
+    Cycle(Vec<PathBuf>),
+}
+
+impl fmt::Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        match self {
+            LoadError::Io(path, err) => write!(f, "error reading '{}': {}", path.display(), err),
+            LoadError::Syntax(path, contents, err) =>
+                write!(f, "syntax error in '{}' at {}", path.display(), err.render(contents)),
+            LoadError::Cycle(paths) => {
+                let names: Vec<String> = paths.iter().map(|p| p.display().to_string()).collect();
+                write!(f, "import cycle: {}", names.join(" -> "))
+            },
+        }
+    }
+}
+
+struct Loader {
+    /// Files already loaded, which later imports skip.
+    loaded: HashSet<PathBuf>,
+    /// Files whose imports are being loaded.
+    stack: Vec<PathBuf>,
+    definitions: Vec<Definition>,
+    prelude_loaded: bool,
+    encoding: Encoding,
+}
+
+impl Loader {
+    fn load_file(&mut self, path: &Path) -> Result<Option<Program>, LoadError> {
+        let canonical = fs::canonicalize(path).map_err(|err| LoadError::Io(path.to_path_buf(), err))?;
+        if let Some(start) = self.stack.iter().position(|p| *p == canonical) {
+            let mut cycle = self.stack[start..].to_vec();
+            cycle.push(canonical);
+            return Err(LoadError::Cycle(cycle));
+        }
+        if self.loaded.contains(&canonical) {
+            return Ok(None);
+        }
+        let contents = fs::read_to_string(&canonical).map_err(|err| LoadError::Io(path.to_path_buf(), err))?;
+        let mut program = match parse_program_with(&contents, self.encoding) {
+            Ok(p) => p,
+            Err(err) => return Err(LoadError::Syntax(path.to_path_buf(), contents, err)),
+        };
+        let dir = canonical.parent().map(Path::to_path_buf).unwrap_or_default();
+        self.stack.push(canonical);
+        for import in &program.imports {
+            if import.path == prelude::NAME {
+                if !self.prelude_loaded {
+                    self.prelude_loaded = true;
+                    self.definitions.extend(prelude::definitions_with(self.encoding));
+                }
+            } else {
+                self.load_file(&dir.join(&import.path))?;
+            }
+        }
+        let canonical = self.stack.pop().unwrap();
+        self.loaded.insert(canonical);
+        self.definitions.append(&mut program.definitions);
+        Ok(Some(program))
+    }
+}
+
+/// Reads a program from a file, resolving imports relative to the importing file,
+/// except that `import "prelude"` refers to the built-in prelude.
+/// The result has no imports; its definitions are those of the imported files,
+/// each loaded once, followed by the file's own. Bodies of imported files are ignored.
+pub fn load(path: &Path) -> Result<Program, LoadError> {
+    load_with(path, Encoding::Church)
+}
+
+/// Like `load`, with number and list literals, and the numerals and lists of
+/// the prelude, in the given encoding.
+pub fn load_with(path: &Path, encoding: Encoding) -> Result<Program, LoadError> {
+    let mut loader = Loader {
+        loaded: HashSet::new(),
+        stack: Vec::new(),
+        definitions: Vec::new(),
+        prelude_loaded: false,
+        encoding,
+    };
+    let program = loader.load_file(path)?.unwrap();
+    Ok(Program { imports: Vec::new(), definitions: loader.definitions, body: program.body })
+}
+
+#[cfg(test)]
+mod tests {
+    use program::*;
+    use expr::lambda::parse;
+
+    use std::env;
+
+    /// Writes `files` into a fresh temporary directory.
+    fn write_files(test: &str, files: &[(&str, &str)]) -> PathBuf {
+        let dir = env::temp_dir().join(format!("lambda-{}-{}", test, std::process::id()));
+        for &(name, contents) in files {
+            let path = dir.join(name);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, contents).unwrap();
+        }
+        dir
+    }
+
+    #[test]
+    fn load_imports() {
+        let dir = write_files("imports", &[
+            ("main.lambda", "import \"lib/bool.lambda\"\nimport \"lib/pair.lambda\"\nfst (pair true false)"),
+            ("lib/bool.lambda", "true a b = a\nfalse a b = b\nunused"),
+            ("lib/pair.lambda", "import \"bool.lambda\"\npair a b f = f a b\nfst p = p true"),
+        ]);
+        let program = load(&dir.join("main.lambda")).unwrap();
+        let names: Vec<&str> = program.definitions.iter().map(|d| &d.name[..]).collect();
+        assert_eq!(vec!["true", "false", "pair", "fst"], names);
+        let (e, _) = program.into_expr().unwrap().repeated_beta(100);
+        assert!(e.alpha_eq(&parse("\\a \\b a").unwrap()));
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn load_prelude() {
+        let dir = write_files("prelude", &[
+            ("main.lambda", "import \"prelude\"\nimport \"lib.lambda\"\nnot (nand true true)"),
+            ("lib.lambda", "import \"prelude\"\nnand a b = not (and a b)"),
+        ]);
+        let program = load(&dir.join("main.lambda")).unwrap();
+        assert_eq!(prelude::definitions().len() + 1, program.definitions.len());
+        let (e, _) = program.into_expr().unwrap().repeated_beta(1000);
+        assert!(e.alpha_eq(&parse("\\a \\b a").unwrap()));
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn load_encoding() {
+        let dir = write_files("encoding", &[
+            ("main.lambda", "import \"lib.lambda\"\nx"),
+            ("lib.lambda", "x = [1]"),
+        ]);
+        let program = load_with(&dir.join("main.lambda"), Encoding::Scott).unwrap();
+        assert_eq!(parse("\\c \\n c (\\s \\z s (\\s \\z z)) (\\c \\n n)").unwrap(), program.into_expr().unwrap().beta().0);
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn load_cycle() {
+        let dir = write_files("cycle", &[
+            ("a.lambda", "import \"b.lambda\"\nx = y"),
+            ("b.lambda", "import \"a.lambda\"\ny = x"),
+        ]);
+        match load(&dir.join("a.lambda")) {
+            Err(LoadError::Cycle(paths)) => {
+                let names: Vec<_> = paths.iter().map(|p| p.file_name().unwrap().to_str().unwrap()).collect();
+                assert_eq!(vec!["a.lambda", "b.lambda", "a.lambda"], names);
+            },
+            r => panic!("expected cycle, got {:?}", r),
+        }
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn load_errors() {
+        let dir = write_files("errors", &[
+            ("main.lambda", "import \"bad.lambda\"\nx"),
+            ("bad.lambda", "x = (y"),
+        ]);
+        match load(&dir.join("main.lambda")) {
+            Err(LoadError::Syntax(path, _, err)) => {
+                assert!(path.ends_with("bad.lambda"));
+                assert_eq!("')'", err.expected);
+            },
+            r => panic!("expected syntax error, got {:?}", r),
+        }
+        assert!(matches!(load(&dir.join("missing.lambda")), Err(LoadError::Io(_, _))));
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
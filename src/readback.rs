@@ -0,0 +1,143 @@
+use encoding::{list_items, pair_items, Decode, Encoding};
+use expr::lambda::LambdaExpr;
+
+use std::fmt;
+
+/// Data recognized in a lambda term.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum Value {
+    Number(u64),
+    Bool(bool),
+    Pair(Box<Value>, Box<Value>),
+    List(Vec<Value>),
+    /// List of numerals that are all character codes.
+    Str(String),
+    /// Term not recognized as data.
+    Term(LambdaExpr),
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        match self {
+            Value::Number(n) => write!(f, "{}", n),
+            Value::Bool(b) => write!(f, "{}", b),
+            Value::Pair(a, b) => write!(f, "({}, {})", a, b),
+            Value::List(items) => {
+                write!(f, "[")?;
+                for (i, item) in items.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{}", item)?;
+                }
+                write!(f, "]")
+            },
+            Value::Str(s) => write!(f, "{:?}", s),
+            Value::Term(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+fn string(items: &[Value]) -> Option<String> {
+    if items.is_empty() {
+        return None;
+    }
+    items.iter().map(|item| match *item {
+        Value::Number(n) if n <= u32::MAX as u64 => ::std::char::from_u32(n as u32)
+            .filter(|c| !c.is_control() || c.is_whitespace()),
+        _ => None,
+    }).collect()
+}
+
+/// Every reading of `expr` as data, up to alpha-equivalence, in the order
+/// number, boolean, pair, list, string. Some terms have several: in the
+/// Church encoding, `λf λx x` is 0, false and the empty list.
+/// Elements of pairs and lists are read with `decode`.
+pub fn readback(expr: &LambdaExpr, encoding: Encoding) -> Vec<Value> {
+    let mut values = Vec::new();
+    if let Some(n) = u64::decode(expr, encoding) {
+        values.push(Value::Number(n));
+    }
+    if let Some(b) = bool::decode(expr, encoding) {
+        values.push(Value::Bool(b));
+    }
+    if let Some((a, b)) = pair_items(expr) {
+        values.push(Value::Pair(Box::new(decode(a, encoding)), Box::new(decode(b, encoding))));
+    }
+    if let Some(items) = list_items(expr, encoding) {
+        let items: Vec<Value> = items.into_iter().map(|e| decode(e, encoding)).collect();
+        if let Some(s) = string(&items) {
+            values.push(Value::List(items));
+            values.push(Value::Str(s));
+        } else {
+            values.push(Value::List(items));
+        }
+    }
+    values
+}
+
+/// The first reading of `expr` by `readback`, or the term itself if there is none.
+pub fn decode(expr: &LambdaExpr, encoding: Encoding) -> Value {
+    readback(expr, encoding).into_iter().next().unwrap_or_else(|| Value::Term(expr.clone()))
+}
+
+#[cfg(test)]
+mod tests {
+    use readback::*;
+    use expr::lambda::{parse, parse_program_with};
+
+    fn read(s: &str) -> Vec<String> {
+        readback(&parse(s).unwrap(), Encoding::Church).iter().map(Value::to_string).collect()
+    }
+
+    fn read_scott(s: &str) -> Vec<String> {
+        let e = parse_program_with(s, Encoding::Scott).unwrap().into_expr().unwrap();
+        readback(&e, Encoding::Scott).iter().map(Value::to_string).collect()
+    }
+
+    #[test]
+    fn numbers() {
+        assert_eq!(vec!["3"], read("\\g \\y g (g (g y))"));
+        assert_eq!(vec!["1"], read("\\f \\x f x"));
+        assert_eq!(vec!["0", "false", "[]"], read("\\f \\x x"));
+        assert_eq!(vec!["2"], read_scott("2"));
+        assert!(read("\\f \\f f (f f)").is_empty());
+        assert!(read("\\f \\x f (g x)").is_empty());
+    }
+
+    #[test]
+    fn booleans() {
+        assert_eq!(vec!["true"], read("\\x \\y x"));
+        assert_eq!(vec!["0", "false", "[]"], read("\\x \\x x"));
+    }
+
+    #[test]
+    fn pairs() {
+        assert_eq!(vec!["(1, true)"], read("\\p p (\\f \\x f x) (\\a \\b a)"));
+        assert_eq!(vec!["(0, λf f)"], read("\\p p (\\f \\x x) (\\f f)"));
+        assert!(read("\\p p p (\\a \\b a)").is_empty());
+    }
+
+    #[test]
+    fn lists() {
+        assert_eq!(vec!["[1, 2, 3]"], read("[1, 2, 3]"));
+        // elements take their first reading
+        assert_eq!(vec!["[0, 0]"], read("[[], 0]"));
+        assert_eq!(vec!["[1, 2, 3]"], read_scott("[1, 2, 3]"));
+        assert_eq!(vec!["[(0, 1)]"], read("[\\f f 0 1]"));
+        assert!(read("\\c \\n c c n").is_empty());
+    }
+
+    #[test]
+    fn strings() {
+        assert_eq!(vec!["[72, 105]", "\"Hi\""], read("[72, 105]"));
+        assert_eq!(vec!["[104, 10]", "\"h\\n\""], read_scott("[104, 10]"));
+        assert_eq!(vec!["[1]"], read("[1]"));
+    }
+
+    #[test]
+    fn decode_term() {
+        assert_eq!(Value::Term(parse("\\x x").unwrap()), decode(&parse("\\x x").unwrap(), Encoding::Church));
+        assert_eq!(Value::Number(0), decode(&parse("\\a \\b b").unwrap(), Encoding::Church));
+    }
+}
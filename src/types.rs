@@ -0,0 +1,610 @@
+use alpha::canonical_name;
+use expr::lambda::{fix, LambdaExpr};
+use expr::systemf::{FExpr, FProgram, FType};
+
+use std::collections::HashSet;
+use std::fmt;
+
+/// Simple type: a type variable or a function type.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum Type {
+    Var(usize),
+    Arrow(Box<Type>, Box<Type>),
+}
+
+impl Type {
+    fn vars(&self, out: &mut Vec<usize>) {
+        match self {
+            Type::Var(v) => if !out.contains(v) { out.push(*v); },
+            Type::Arrow(t1, t2) => { t1.vars(out); t2.vars(out); },
+        }
+    }
+
+    fn rename(&self, order: &[usize]) -> Type {
+        match self {
+            Type::Var(v) => Type::Var(order.iter().position(|w| w == v).unwrap()),
+            Type::Arrow(t1, t2) => Type::Arrow(Box::new(t1.rename(order)), Box::new(t2.rename(order))),
+        }
+    }
+
+    /// Replaces the variables given in `types`.
+    fn instantiate(&self, types: &[(usize, Type)]) -> Type {
+        match self {
+            Type::Var(v) => match types.iter().find(|(w, _)| w == v) {
+                Some((_, t)) => t.clone(),
+                None => self.clone(),
+            },
+            Type::Arrow(t1, t2) => Type::Arrow(Box::new(t1.instantiate(types)), Box::new(t2.instantiate(types))),
+        }
+    }
+}
+
+/// Type of a bound variable, polymorphic in `vars`, which are never
+/// unified; each use gets its own copy of them.
+struct Scheme {
+    vars: Vec<usize>,
+    ty: Type,
+}
+
+impl Scheme {
+    fn mono(ty: Type) -> Scheme {
+        Scheme { vars: Vec::new(), ty }
+    }
+}
+
+impl fmt::Display for Type {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        match self {
+            Type::Var(v) => write!(f, "{}", canonical_name(*v)),
+            Type::Arrow(t1, t2) => match **t1 {
+                Type::Arrow(_, _) => write!(f, "({}) -> {}", t1, t2),
+                Type::Var(_) => write!(f, "{} -> {}", t1, t2),
+            },
+        }
+    }
+}
+
+/// Principal type of a term, along with the types its free variables must have.
+/// Type variables are numbered in order of appearance, type first.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Typing {
+    /// Free variables in order of appearance.
+    pub free: Vec<(String, Type)>,
+    pub ty: Type,
+}
+
+impl fmt::Display for Typing {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        if !self.free.is_empty() {
+            let context: Vec<String> = self.free.iter().map(|(v, t)| format!("{} : {}", v, t)).collect();
+            write!(f, "{} ⊢ ", context.join(", "))?;
+        }
+        write!(f, "{}", self.ty)
+    }
+}
+
+/// A term is not typeable: some application, or `letrec` value, would need
+/// the type variable `var` to equal `ty`, which contains it.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct TypeError {
+    /// Application or `letrec` value where the occurs check failed.
+    pub expr: LambdaExpr,
+    pub var: Type,
+    pub ty: Type,
+}
+
+impl fmt::Display for TypeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        write!(f, "infinite type {} = {} in {}", self.var, self.ty, self.expr)
+    }
+}
+
+struct Inferer {
+    /// Type each variable is bound to by unification, if any.
+    subst: Vec<Option<Type>>,
+    /// Fixpoint combinator `letrec` is desugared with.
+    fix: LambdaExpr,
+}
+
+impl Inferer {
+    fn fresh(&mut self) -> Type {
+        self.subst.push(None);
+        Type::Var(self.subst.len() - 1)
+    }
+
+    /// `t` with bound variables at the top replaced.
+    fn resolve(&self, t: &Type) -> Type {
+        match t {
+            Type::Var(v) => match self.subst[*v] {
+                Some(ref t) => self.resolve(t),
+                None => t.clone(),
+            },
+            t => t.clone(),
+        }
+    }
+
+    /// `t` with all bound variables replaced.
+    fn zonk(&self, t: &Type) -> Type {
+        match self.resolve(t) {
+            Type::Arrow(t1, t2) => Type::Arrow(Box::new(self.zonk(&t1)), Box::new(self.zonk(&t2))),
+            t => t,
+        }
+    }
+
+    fn occurs(&self, v: usize, t: &Type) -> bool {
+        match self.resolve(t) {
+            Type::Var(w) => v == w,
+            Type::Arrow(t1, t2) => self.occurs(v, &t1) || self.occurs(v, &t2),
+        }
+    }
+
+    /// Unifies the two types, or returns the variable and type that failed the occurs check.
+    fn unify(&mut self, t1: &Type, t2: &Type) -> Result<(), (Type, Type)> {
+        match (self.resolve(t1), self.resolve(t2)) {
+            (Type::Var(v), Type::Var(w)) if v == w => Ok(()),
+            (Type::Var(v), t) | (t, Type::Var(v)) => {
+                if self.occurs(v, &t) {
+                    return Err((Type::Var(v), t));
+                }
+                self.subst[v] = Some(t);
+                Ok(())
+            },
+            (Type::Arrow(a1, r1), Type::Arrow(a2, r2)) => {
+                self.unify(&a1, &a2)?;
+                self.unify(&r1, &r2)
+            },
+        }
+    }
+
+    /// Error for an occurs check of `var` against `ty` failing in `expr`.
+    fn error(&self, expr: &LambdaExpr, var: &Type, ty: &Type) -> TypeError {
+        let (var, ty) = (self.zonk(var), self.zonk(ty));
+        let mut order = Vec::new();
+        var.vars(&mut order);
+        ty.vars(&mut order);
+        TypeError { expr: expr.clone(), var: var.rename(&order), ty: ty.rename(&order) }
+    }
+
+    /// `ty` polymorphic in the variables that no type in `env` or `free` mentions.
+    fn generalize(&self, ty: &Type, env: &[(&str, Scheme)], free: &[(String, Type)]) -> Scheme {
+        let mut fixed = Vec::new();
+        for (_, scheme) in env {
+            let mut vars = Vec::new();
+            self.zonk(&scheme.ty).vars(&mut vars);
+            fixed.extend(vars.into_iter().filter(|v| !scheme.vars.contains(v)));
+        }
+        for (_, t) in free {
+            self.zonk(t).vars(&mut fixed);
+        }
+        let ty = self.zonk(ty);
+        let mut vars = Vec::new();
+        ty.vars(&mut vars);
+        vars.retain(|v| !fixed.contains(v));
+        Scheme { vars, ty }
+    }
+
+    /// Type of the value bound to `name` by a `let`, or by a `letrec`,
+    /// which desugars to `fix (λname value)`.
+    fn infer_bound<'a>(&mut self, name: &str, value: &'a LambdaExpr, env: &mut Vec<(&'a str, Scheme)>,
+                       free: &mut Vec<(String, Type)>) -> Result<Type, TypeError> {
+        if let LambdaExpr::Apply(ref f, ref e) = *value {
+            if let LambdaExpr::Lambda(ref v, ref body) = **e {
+                if v == name && **f == self.fix {
+                    let ty = self.fresh();
+                    env.push((v, Scheme::mono(ty.clone())));
+                    let body = self.infer(body, env, free);
+                    env.pop();
+                    self.unify(&ty, &body?).map_err(|(var, t)| self.error(value, &var, &t))?;
+                    return Ok(ty);
+                }
+            }
+        }
+        self.infer(value, env, free)
+    }
+
+    fn infer<'a>(&mut self, expr: &'a LambdaExpr, env: &mut Vec<(&'a str, Scheme)>,
+                 free: &mut Vec<(String, Type)>) -> Result<Type, TypeError> {
+        match expr {
+            LambdaExpr::Variable(v) => {
+                if let Some((_, scheme)) = env.iter().rev().find(|(w, _)| w == v) {
+                    let types: Vec<(usize, Type)> = scheme.vars.iter().map(|&w| (w, self.fresh())).collect();
+                    return Ok(scheme.ty.instantiate(&types));
+                }
+                if let Some((_, t)) = free.iter().find(|(w, _)| w == v) {
+                    return Ok(t.clone());
+                }
+                let t = self.fresh();
+                free.push((v.clone(), t.clone()));
+                Ok(t)
+            },
+            LambdaExpr::Apply(e1, e2) => {
+                if let LambdaExpr::Lambda(ref v, ref body) = **e1 {
+                    // `let v = e2 in body`, so uses of `v` may differ in type
+                    let value = self.infer_bound(v, e2, env, free)?;
+                    let scheme = self.generalize(&value, env, free);
+                    env.push((v, scheme));
+                    let body = self.infer(body, env, free);
+                    env.pop();
+                    return body;
+                }
+                let t1 = self.infer(e1, env, free)?;
+                let t2 = self.infer(e2, env, free)?;
+                let result = self.fresh();
+                let expected = Type::Arrow(Box::new(t2), Box::new(result.clone()));
+                match self.unify(&t1, &expected) {
+                    Ok(()) => Ok(result),
+                    Err((var, ty)) => Err(self.error(expr, &var, &ty)),
+                }
+            },
+            LambdaExpr::Lambda(v, e) => {
+                let param = self.fresh();
+                env.push((v, Scheme::mono(param.clone())));
+                let body = self.infer(e, env, free);
+                env.pop();
+                Ok(Type::Arrow(Box::new(param), Box::new(body?)))
+            },
+        }
+    }
+}
+
+impl LambdaExpr {
+    /// Infers the principal type of the term, giving each free variable a
+    /// single type. Since `let` is sugar for an application, every redex
+    /// `(λx e) v` is typed as `let x = v in e`, with the type of `v`
+    /// generalized as in Hindley-Milner, so e.g. `(λi i i) (λx x)` has type
+    /// `a -> a`; a `letrec` value `fix (λx v)` is typed with `x` bound to a
+    /// single type in `v`. Other terms get their principal simple type.
+    pub fn type_of(&self) -> Result<Typing, TypeError> {
+        let mut inferer = Inferer { subst: Vec::new(), fix: fix() };
+        let mut free = Vec::new();
+        let ty = inferer.infer(self, &mut Vec::new(), &mut free)?;
+        let ty = inferer.zonk(&ty);
+        let free: Vec<(String, Type)> = free.into_iter().map(|(v, t)| (v, inferer.zonk(&t))).collect();
+        let mut order = Vec::new();
+        ty.vars(&mut order);
+        for (_, t) in &free {
+            t.vars(&mut order);
+        }
+        Ok(Typing {
+            free: free.into_iter().map(|(v, t)| (v, t.rename(&order))).collect(),
+            ty: ty.rename(&order),
+        })
+    }
+}
+
+/// `base` with `'` appended until `taken` rejects it no more.
+fn fresh(base: &str, taken: impl Fn(&str) -> bool) -> String {
+    let mut name = base.to_string();
+    while taken(&name) {
+        name.push('\'');
+    }
+    name
+}
+
+impl FType {
+    pub fn has_free(&self, var: &str) -> bool {
+        match self {
+            FType::Var(v) => v == var,
+            FType::Arrow(t1, t2) => t1.has_free(var) || t2.has_free(var),
+            FType::Forall(v, t) => v != var && t.has_free(var),
+        }
+    }
+
+    fn names<'a>(&'a self, out: &mut HashSet<&'a str>) {
+        match self {
+            FType::Var(v) => { out.insert(v); },
+            FType::Arrow(t1, t2) => { t1.names(out); t2.names(out); },
+            FType::Forall(v, t) => { out.insert(v); t.names(out); },
+        }
+    }
+
+    /// Replaces free occurrences of `var` with `ty`, renaming bound variables to avoid capture.
+    pub fn subst(&self, var: &str, ty: &FType) -> FType {
+        match self {
+            FType::Var(v) => if v == var { ty.clone() } else { self.clone() },
+            FType::Arrow(t1, t2) => FType::Arrow(Box::new(t1.subst(var, ty)), Box::new(t2.subst(var, ty))),
+            FType::Forall(v, t) => {
+                if v == var {
+                    self.clone()
+                } else if ty.has_free(v) {
+                    let mut names = HashSet::new();
+                    t.names(&mut names);
+                    let name = fresh(v, |n| names.contains(n) || ty.has_free(n));
+                    let t = t.subst(v, &FType::Var(name.clone()));
+                    FType::Forall(name, Box::new(t.subst(var, ty)))
+                } else {
+                    FType::Forall(v.clone(), Box::new(t.subst(var, ty)))
+                }
+            },
+        }
+    }
+
+    /// Whether the two types are equal up to renaming of bound type variables.
+    pub fn alpha_eq(&self, other: &FType) -> bool {
+        self.alpha_eq_in(other, &mut Vec::new(), &mut Vec::new())
+    }
+
+    fn alpha_eq_in<'a>(&'a self, other: &'a FType, names1: &mut Vec<&'a str>, names2: &mut Vec<&'a str>) -> bool {
+        match (self, other) {
+            (FType::Var(v1), FType::Var(v2)) =>
+                match (names1.iter().rev().position(|n| n == v1), names2.iter().rev().position(|n| n == v2)) {
+                    (None, None) => v1 == v2,
+                    (i, j) => i == j,
+                },
+            (FType::Arrow(a1, r1), FType::Arrow(a2, r2)) =>
+                a1.alpha_eq_in(a2, names1, names2) && r1.alpha_eq_in(r2, names1, names2),
+            (FType::Forall(v1, t1), FType::Forall(v2, t2)) => {
+                names1.push(v1);
+                names2.push(v2);
+                let res = t1.alpha_eq_in(t2, names1, names2);
+                names1.pop();
+                names2.pop();
+                res
+            },
+            _ => false,
+        }
+    }
+}
+
+impl FExpr {
+    fn type_names<'a>(&'a self, out: &mut HashSet<&'a str>) {
+        match self {
+            FExpr::Variable(_) => {},
+            FExpr::Apply(e1, e2) => { e1.type_names(out); e2.type_names(out); },
+            FExpr::Lambda(_, t, e) => { t.names(out); e.type_names(out); },
+            FExpr::TypeLambda(v, e) => { out.insert(v); e.type_names(out); },
+            FExpr::TypeApply(e, t) => { e.type_names(out); t.names(out); },
+        }
+    }
+
+    /// Replaces free occurrences of the type variable `var` with `ty`.
+    pub fn subst_type(&self, var: &str, ty: &FType) -> FExpr {
+        match self {
+            FExpr::Variable(_) => self.clone(),
+            FExpr::Apply(e1, e2) => FExpr::Apply(Box::new(e1.subst_type(var, ty)), Box::new(e2.subst_type(var, ty))),
+            FExpr::Lambda(v, t, e) => FExpr::Lambda(v.clone(), t.subst(var, ty), Box::new(e.subst_type(var, ty))),
+            FExpr::TypeApply(e, t) => FExpr::TypeApply(Box::new(e.subst_type(var, ty)), t.subst(var, ty)),
+            FExpr::TypeLambda(v, e) => {
+                if v == var {
+                    self.clone()
+                } else if ty.has_free(v) {
+                    let mut names = HashSet::new();
+                    e.type_names(&mut names);
+                    let name = fresh(v, |n| names.contains(n) || ty.has_free(n));
+                    let e = e.subst_type(v, &FType::Var(name.clone()));
+                    FExpr::TypeLambda(name, Box::new(e.subst_type(var, ty)))
+                } else {
+                    FExpr::TypeLambda(v.clone(), Box::new(e.subst_type(var, ty)))
+                }
+            },
+        }
+    }
+
+    /// Type of the term in System F, where each free variable must be given in `context`.
+    pub fn type_check(&self, context: &[(String, FType)]) -> Result<FType, FTypeError> {
+        let mut context = context.to_vec();
+        self.check(&mut context)
+    }
+
+    fn check(&self, context: &mut Vec<(String, FType)>) -> Result<FType, FTypeError> {
+        match self {
+            FExpr::Variable(v) => context.iter().rev().find(|(w, _)| w == v).map(|(_, t)| t.clone())
+                .ok_or_else(|| FTypeError::Unbound(v.clone())),
+            FExpr::Lambda(v, t, e) => {
+                context.push((v.clone(), t.clone()));
+                let body = e.check(context);
+                context.pop();
+                Ok(FType::Arrow(Box::new(t.clone()), Box::new(body?)))
+            },
+            FExpr::Apply(e1, e2) => match e1.check(context)? {
+                FType::Arrow(param, result) => {
+                    let arg = e2.check(context)?;
+                    if !arg.alpha_eq(&param) {
+                        return Err(FTypeError::Mismatch { expr: e2.clone(), expected: *param, found: arg });
+                    }
+                    Ok(*result)
+                },
+                ty => Err(FTypeError::NotFunction { expr: e1.clone(), ty }),
+            },
+            FExpr::TypeLambda(v, e) => {
+                if context.iter().any(|(_, t)| t.has_free(v)) {
+                    // the variable must be fresh, so rename it
+                    let mut names = HashSet::new();
+                    e.type_names(&mut names);
+                    let name = fresh(v, |n| names.contains(n) || context.iter().any(|(_, t)| t.has_free(n)));
+                    let e = e.subst_type(v, &FType::Var(name.clone()));
+                    Ok(FType::Forall(name, Box::new(e.check(context)?)))
+                } else {
+                    Ok(FType::Forall(v.clone(), Box::new(e.check(context)?)))
+                }
+            },
+            FExpr::TypeApply(e, t) => match e.check(context)? {
+                FType::Forall(v, body) => Ok(body.subst(&v, t)),
+                ty => Err(FTypeError::NotForall { expr: e.clone(), ty }),
+            },
+        }
+    }
+}
+
+impl FProgram {
+    /// Checks each definition in order, against its type if given, and then the body.
+    /// Returns the types of the definitions and of the body.
+    pub fn type_check(&self) -> Result<(Vec<FType>, Option<FType>), FTypeError> {
+        let mut context = Vec::new();
+        let mut types = Vec::new();
+        for def in &self.definitions {
+            let ty = def.value.check(&mut context)?;
+            if let Some(ref expected) = def.ty {
+                if !ty.alpha_eq(expected) {
+                    return Err(FTypeError::Mismatch { expr: Box::new(def.value.clone()), expected: expected.clone(), found: ty });
+                }
+            }
+            context.push((def.name.clone(), ty.clone()));
+            types.push(ty);
+        }
+        let body = match self.body {
+            Some(ref e) => Some(e.check(&mut context)?),
+            None => None,
+        };
+        Ok((types, body))
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum FTypeError {
+    Unbound(String),
+    /// Term applied to an argument whose type is not a function type.
+    NotFunction { expr: Box<FExpr>, ty: FType },
+    /// Term applied to a type whose type is not universal.
+    NotForall { expr: Box<FExpr>, ty: FType },
+    Mismatch { expr: Box<FExpr>, expected: FType, found: FType },
+}
+
+impl fmt::Display for FTypeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        match self {
+            FTypeError::Unbound(v) => write!(f, "unbound variable {}", v),
+            FTypeError::NotFunction { expr, ty } => write!(f, "{} has type {}, which is not a function type", expr, ty),
+            FTypeError::NotForall { expr, ty } => write!(f, "{} has type {}, which is not a universal type", expr, ty),
+            FTypeError::Mismatch { expr, expected, found } =>
+                write!(f, "{} has type {}, expected {}", expr, found, expected),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use types::*;
+    use expr::lambda::parse;
+    use expr::systemf;
+    use prelude;
+
+    fn type_of(s: &str) -> String {
+        match parse(s).unwrap().type_of() {
+            Ok(t) => t.to_string(),
+            Err(err) => err.to_string(),
+        }
+    }
+
+    #[test]
+    fn combinators() {
+        assert_eq!("a -> a", type_of("\\x x"));
+        assert_eq!("a -> b -> a", type_of("\\x \\y x"));
+        assert_eq!("(a -> b -> c) -> (a -> b) -> a -> c", type_of("\\x \\y \\z x z (y z)"));
+        assert_eq!("(a -> b) -> (c -> a) -> c -> b", type_of("\\f \\g \\x f (g x)"));
+    }
+
+    #[test]
+    fn shadowing() {
+        assert_eq!("a -> b -> b", type_of("\\x \\x x"));
+    }
+
+    #[test]
+    fn church_numerals() {
+        assert_eq!("(a -> a) -> a -> a", type_of("2"));
+        assert_eq!("a -> b -> b", type_of("0"));
+        assert_eq!("((a -> b) -> c -> a) -> (a -> b) -> c -> b", type_of("\\n \\f \\x f (n f x)"));
+    }
+
+    #[test]
+    fn free_variables() {
+        assert_eq!("f : b -> a, x : b ⊢ a", type_of("f x"));
+        assert_eq!("f : a -> a -> b ⊢ a -> b", type_of("\\x f x x"));
+    }
+
+    #[test]
+    fn not_typeable() {
+        assert_eq!("infinite type a = a -> b in x x", type_of("\\x x x"));
+        assert!(prelude::parse("Y").unwrap().type_of().is_err());
+        assert!(parse(include_str!("../factorial.lambda")).unwrap().type_of().is_err());
+        // variables bound by lambdas or free are not generalized
+        assert_eq!("infinite type a = a -> b in g g", type_of("\\f let g = f in g g"));
+        assert_eq!("infinite type a = a -> b in g g", type_of("let g = f in g g"));
+    }
+
+    #[test]
+    fn let_polymorphism() {
+        assert_eq!("a -> a", type_of("let i = \\x x in i i"));
+        assert_eq!("a -> a", type_of("(\\i i i) (\\x x)"));
+        assert_eq!("a -> b -> a", type_of("let k = \\x \\y x in k (k k k) k"));
+        // each definition is generalized before the next one uses it
+        assert_eq!("a -> a", type_of("id x = x\ntwice f x = f (f x)\ntwice id (twice id)"));
+        assert_eq!("f : a ⊢ a", type_of("let g = \\x f in g g"));
+    }
+
+    #[test]
+    fn letrec() {
+        assert_eq!("a -> b", type_of("letrec f n = f n in f"));
+        assert_eq!("(a -> a) -> a -> a", type_of("letrec f n = f n in (\\x \\y x) 2 (f f)"));
+        assert_eq!("infinite type a = a -> b in f f", type_of("letrec f = f f in f"));
+    }
+
+    #[test]
+    fn reduction_preserves_type() {
+        let e = parse("(\\x \\y x) (\\z z)").unwrap();
+        let t = e.type_of().unwrap();
+        let (e, _) = e.repeated_beta(1000);
+        assert_eq!(t, e.type_of().unwrap());
+    }
+
+    fn f_type(s: &str) -> String {
+        match systemf::parse(s).unwrap().type_check(&[]) {
+            Ok(t) => t.to_string(),
+            Err(err) => err.to_string(),
+        }
+    }
+
+    const NAT: &str = "
+type Nat = ∀a. (a -> a) -> a -> a
+zero : Nat = Λa. λf:a -> a. λx:a. x
+succ : Nat -> Nat = λn:Nat. Λa. λf:a -> a. λx:a. f (n [a] f x)
+add : Nat -> Nat -> Nat = λm:Nat. λn:Nat. m [Nat] succ n
+mul : Nat -> Nat -> Nat = λm:Nat. λn:Nat. m [Nat] (add n) zero
+";
+
+    #[test]
+    fn system_f_polymorphism() {
+        assert_eq!("∀a. a -> a", f_type("Λa. λx:a. x"));
+        assert_eq!("(∀a. a -> a) -> ∀a. a -> a", f_type("λi:∀a. a -> a. i [∀a. a -> a] i"));
+        assert_eq!("c -> c", f_type("(Λa. λx:a. x) [c]"));
+        assert_eq!("(∀b. b -> b) -> ∀b. b -> b", f_type("(Λa. λx:∀b. b -> b. x) [c]"));
+    }
+
+    #[test]
+    fn system_f_capture() {
+        // instantiating a with b must not capture the inner b
+        assert_eq!("∀b'. b -> b' -> b", f_type("(Λa. Λb. λx:a. λy:b. x) [b]"));
+        // the inner a must not capture the type of x
+        assert_eq!("∀a. a -> ∀a'. a' -> a", f_type("Λa. λx:a. Λa. λy:a. x"));
+        assert_eq!("b -> ∀a'. a' -> b", f_type("(Λa. λx:a. Λa. λy:a. x) [b]"));
+    }
+
+    #[test]
+    fn system_f_errors() {
+        assert_eq!("unbound variable y", f_type("λx:a. y"));
+        assert_eq!("x has type a, which is not a function type", f_type("λx:a. x x"));
+        assert_eq!("x has type a, which is not a universal type", f_type("λx:a. x [a]"));
+        assert_eq!("y has type b, expected a", f_type("λf:a -> a. λy:b. f y"));
+    }
+
+    #[test]
+    fn system_f_church_numerals() {
+        let program = systemf::parse_program(&format!("{}mul (succ (succ zero)) (succ (succ (succ zero)))", NAT)).unwrap();
+        let (types, body) = program.type_check().unwrap();
+        assert_eq!(4, types.len());
+        assert!(body.unwrap().alpha_eq(&systemf::parse_type("∀b. (b -> b) -> b -> b").unwrap()));
+        let (e, _) = program.erase().unwrap().repeated_beta(10000);
+        assert!(e.alpha_eq(&parse("6").unwrap()));
+    }
+
+    #[test]
+    fn system_f_wrong_annotation() {
+        let program = systemf::parse_program(&format!("{}pred : Nat -> Nat = λn:Nat. n", NAT)).unwrap();
+        assert!(program.type_check().is_ok());
+        let program = systemf::parse_program(&format!("{}bad : Nat = succ", NAT)).unwrap();
+        match program.type_check() {
+            Err(FTypeError::Mismatch { expected, .. }) => assert_eq!("∀a. (a -> a) -> a -> a", expected.to_string()),
+            r => panic!("expected mismatch, got {:?}", r),
+        }
+    }
+}